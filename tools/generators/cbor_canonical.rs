@@ -1,16 +1,29 @@
 use serde::{ser::SerializeMap, ser::SerializeSeq, Serialize};
 use serde_cbor::value::{to_value, Value};
 
-/// Encode a serde-serializable value into canonical CBOR bytes (RFC 8949 ordering).
+/// Which canonical CBOR map-key ordering rule to apply. Both orderings sort
+/// by the keys' own encoded CBOR bytes and both preserve definite-length
+/// encoding; only the comparator differs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanonicalMode {
+    /// CTAP2 canonical CBOR: the shorter encoded key sorts first, ties
+    /// broken by bytewise comparison.
+    Ctap2,
+    /// RFC 8949 §4.2.1 core deterministic encoding: pure bytewise order of
+    /// the encoded key bytes, with no length-first comparison.
+    Rfc8949,
+}
+
+/// Encode a serde-serializable value into canonical CBOR bytes.
 ///
-/// Map keys are sorted by their canonical CBOR encoding: shortest first, then
-/// lexicographic. Nested arrays/maps are canonicalized recursively.
-pub fn encode_canonical<T: Serialize>(value: &T) -> Result<Vec<u8>, serde_cbor::Error> {
+/// Map keys are sorted according to `mode`; nested arrays/maps are
+/// canonicalized recursively under the same mode.
+pub fn encode_canonical<T: Serialize>(value: &T, mode: CanonicalMode) -> Result<Vec<u8>, serde_cbor::Error> {
     let val = to_value(value)?;
-    serde_cbor::to_vec(&CanonValue(&val))
+    serde_cbor::to_vec(&CanonValue(&val, mode))
 }
 
-struct CanonValue<'a>(&'a Value);
+struct CanonValue<'a>(&'a Value, CanonicalMode);
 
 impl<'a> Serialize for CanonValue<'a> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -21,7 +34,7 @@ impl<'a> Serialize for CanonValue<'a> {
             Value::Array(items) => {
                 let mut seq = serializer.serialize_seq(Some(items.len()))?;
                 for item in items {
-                    seq.serialize_element(&CanonValue(item))?;
+                    seq.serialize_element(&CanonValue(item, self.1))?;
                 }
                 seq.end()
             }
@@ -29,14 +42,19 @@ impl<'a> Serialize for CanonValue<'a> {
                 // Map is BTreeMap; we re-sort entries by canonical CBOR key bytes.
                 let mut entries = Vec::with_capacity(map.len());
                 for (k, v) in map.iter() {
-                    let key_bytes = serde_cbor::to_vec(&CanonValue(k)).map_err(serde::ser::Error::custom)?;
+                    let key_bytes = serde_cbor::to_vec(&CanonValue(k, self.1)).map_err(serde::ser::Error::custom)?;
                     entries.push((key_bytes, k, v));
                 }
-                entries.sort_by(|(kb1, _, _), (kb2, _, _)| kb1.len().cmp(&kb2.len()).then_with(|| kb1.cmp(kb2)));
+                match self.1 {
+                    CanonicalMode::Ctap2 => {
+                        entries.sort_by(|(kb1, _, _), (kb2, _, _)| kb1.len().cmp(&kb2.len()).then_with(|| kb1.cmp(kb2)))
+                    }
+                    CanonicalMode::Rfc8949 => entries.sort_by(|(kb1, _, _), (kb2, _, _)| kb1.cmp(kb2)),
+                }
 
                 let mut map_ser = serializer.serialize_map(Some(entries.len()))?;
                 for (_, k, v) in entries {
-                    map_ser.serialize_entry(&CanonValue(k), &CanonValue(v))?;
+                    map_ser.serialize_entry(&CanonValue(k, self.1), &CanonValue(v, self.1))?;
                 }
                 map_ser.end()
             }
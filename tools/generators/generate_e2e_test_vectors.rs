@@ -1,12 +1,113 @@
 use base64::{engine::general_purpose, Engine as _};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use pqcrypto_kyber::kyber1024 as kyber;
+use pqcrypto_traits::kem::{Ciphertext, PublicKey as KyberPublicKeyTrait, SharedSecret};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::error::Error;
 use std::fs;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Uniform padding-length distribution in bytes, inclusive of both ends.
+/// Kept as a plain min/max today; the shape is pluggable (e.g. a geometric
+/// distribution matching real traffic) without changing the frame format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PadDistribution {
+    pub min_bytes: usize,
+    pub max_bytes: usize,
+}
+
+/// Obfuscation mode for the handshake transport, modeled on pluggable
+/// transports (obfs4/o5): either the message goes out as-is (`Plain`), or it
+/// is wrapped in a padded, length-hidden, MAC'd frame (`Obfuscated`) so a
+/// passive observer sees no fixed-size Kyber key and can't even read the
+/// frame length.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode")]
+pub enum FramingMode {
+    Plain,
+    Obfuscated {
+        /// Shared node/bridge secret (hex) the length-prefix cipher and MAC
+        /// keys are derived from. Out-of-band, not part of the PQ-hybrid
+        /// handshake itself.
+        node_key: String,
+        pad_dist: PadDistribution,
+    },
+}
+
+/// `frame_body || mac`, where `frame_body = enc_len_prefix || padding || payload`.
+/// `enc_len_prefix` is the real `payload` length (4 bytes, little-endian)
+/// XORed with an HKDF keystream, so the true on-wire size is never visible
+/// to a passive observer even though `frame_body` itself has random length.
+fn obfuscate_frame(node_key: &[u8], pad_dist: &PadDistribution, rng: &mut ChaCha20Rng, payload: &[u8]) -> Vec<u8> {
+    let hk = Hkdf::<Sha256>::new(None, node_key);
+
+    let mut len_keystream = [0u8; 4];
+    hk.expand(b"FoxWhisper-ObfsLen", &mut len_keystream)
+        .expect("4 bytes is a valid HKDF-Expand length for SHA-256");
+    let len_bytes = (payload.len() as u32).to_le_bytes();
+    let enc_len_prefix: Vec<u8> = len_bytes
+        .iter()
+        .zip(len_keystream.iter())
+        .map(|(b, k)| b ^ k)
+        .collect();
+
+    let pad_len = rng.gen_range(pad_dist.min_bytes..=pad_dist.max_bytes);
+    let mut padding = vec![0u8; pad_len];
+    rng.fill(&mut padding[..]);
+
+    let mut frame_body = Vec::with_capacity(enc_len_prefix.len() + padding.len() + payload.len());
+    frame_body.extend_from_slice(&enc_len_prefix);
+    frame_body.extend_from_slice(&padding);
+    frame_body.extend_from_slice(payload);
+
+    let mut mac_key = [0u8; 32];
+    hk.expand(b"FoxWhisper-ObfsMac", &mut mac_key)
+        .expect("32 bytes is a valid HKDF-Expand length for SHA-256");
+    let mut mac = HmacSha256::new_from_slice(&mac_key).expect("HMAC accepts any key length");
+    mac.update(&frame_body);
+    let tag = mac.finalize().into_bytes();
+
+    let mut frame = frame_body;
+    frame.extend_from_slice(&tag[..16]);
+    frame
+}
+
+/// Wraps `payload` per `mode`, returning the exact bytes that would go on
+/// the wire. `Plain` is a no-op so the framing layer stays optional.
+fn apply_framing(mode: &FramingMode, rng: &mut ChaCha20Rng, payload: &[u8]) -> Vec<u8> {
+    match mode {
+        FramingMode::Plain => payload.to_vec(),
+        FramingMode::Obfuscated { node_key, pad_dist } => {
+            let node_key_bytes = hex::decode(node_key).expect("node_key is valid hex");
+            obfuscate_frame(&node_key_bytes, pad_dist, rng, payload)
+        }
+    }
+}
 
 // FoxWhisper End-to-End Test Vector Generator (Rust)
 // Generates complete protocol flow test vectors for FoxWhisper v0.9
 
+/// Fixed seed for the ids, nonces, X25519 ephemeral keys and obfuscation
+/// padding, so those values are the same on every run. The Kyber keypair
+/// and encapsulation are not drawn from this seed (see
+/// `generate_handshake_flow`).
+const TEST_SEED: u64 = 0x466F_7857_6869_7370;
+
+/// The bytes of a message as hashed into `handshake_hash` (and framed into
+/// `wire_frame`): compact `serde_json` output in `HandshakeMessage`'s field
+/// order with `None` fields omitted. Another implementation reproduces the
+/// transcript only by producing this exact JSON serialization.
+fn transcript_bytes(message: &HandshakeMessage) -> Vec<u8> {
+    serde_json::to_vec(message).expect("handshake message always serializes")
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct HandshakeMessage {
     #[serde(rename = "type")]
@@ -43,6 +144,10 @@ pub struct HandshakeStep {
     pub message: HandshakeMessage,
     #[serde(rename = "expected_response")]
     pub expected_response: String,
+    /// Base64 on-wire bytes for this step under the flow's `framing_mode`.
+    /// Absent when the flow was generated with `FramingMode::Plain`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wire_frame: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -50,6 +155,24 @@ pub struct HandshakeFlow {
     pub description: String,
     pub participants: Vec<String>,
     pub steps: Vec<HandshakeStep>,
+    /// Intermediate secrets from the hybrid key schedule, exposed so other
+    /// implementations can check each derivation step rather than only the
+    /// final `session_id`.
+    pub derived_secrets: DerivedSecrets,
+    /// The obfuscation mode every `wire_frame` in `steps` was produced with.
+    pub framing_mode: FramingMode,
+}
+
+/// Intermediate values of the FoxWhisper hybrid key schedule:
+/// `ss = ss_x25519 || ss_kyber`, then HKDF-Extract/Expand keyed by the
+/// transcript hash of steps 1 and 2.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DerivedSecrets {
+    pub ss_x25519: String,
+    pub ss_kyber: String,
+    pub handshake_hash: String,
+    pub session_id: String,
+    pub chaining_secret: String,
 }
 
 pub struct EndToEndTestVectorGenerator {
@@ -63,18 +186,143 @@ impl EndToEndTestVectorGenerator {
         }
     }
 
-    pub fn generate_handshake_flow(&mut self) -> HandshakeFlow {
-        // Generate cryptographic material
-        let client_id = Some(generate_random_base64(32));
-        let server_id = Some(generate_random_base64(32));
-        let client_x25519_pub = generate_random_base64(32);
-        let server_x25519_pub = generate_random_base64(32);
-        let client_kyber_pub = Some(generate_random_base64(1568));
-        let server_kyber_cipher = Some(generate_random_base64(1568));
-        let client_nonce = Some(generate_random_base64(16));
-        let server_nonce = Some(generate_random_base64(16));
-        let session_id = Some(generate_random_base64(32));
-        let handshake_hash = Some(generate_random_base64(32));
+    pub fn generate_handshake_flow(&mut self, framing: FramingMode) -> HandshakeFlow {
+        // Ids, nonces, X25519 ephemeral keys and framing padding are drawn
+        // from a single seeded RNG. `kyber::keypair`/`kyber::encapsulate`
+        // use pqcrypto's own system randomness instead, so the Kyber key,
+        // ciphertext, `ss_kyber`, and everything derived from them
+        // (`handshake_hash`, `session_id`, `chaining_secret`, wire frames)
+        // differ from run to run.
+        let mut rng = ChaCha20Rng::seed_from_u64(TEST_SEED);
+
+        let client_id = Some(generate_seeded_base64(&mut rng, 32));
+        let server_id = Some(generate_seeded_base64(&mut rng, 32));
+        let client_nonce = Some(generate_seeded_base64(&mut rng, 16));
+        let server_nonce = Some(generate_seeded_base64(&mut rng, 16));
+
+        // Real X25519 ephemeral key agreement.
+        let client_x25519_secret = StaticSecret::random_from_rng(&mut rng);
+        let client_x25519_pub = X25519PublicKey::from(&client_x25519_secret);
+        let server_x25519_secret = StaticSecret::random_from_rng(&mut rng);
+        let server_x25519_pub = X25519PublicKey::from(&server_x25519_secret);
+        let ss_x25519 = client_x25519_secret.diffie_hellman(&server_x25519_pub);
+
+        // Real ML-KEM (Kyber) encapsulation: the server encapsulates against
+        // the client's public key, and both sides end up with `ss_kyber`.
+        let (client_kyber_pub, client_kyber_secret) = kyber::keypair();
+        let (ss_kyber_server, kyber_ciphertext) = kyber::encapsulate(&client_kyber_pub);
+        let ss_kyber_client = kyber::decapsulate(&kyber_ciphertext, &client_kyber_secret);
+        debug_assert_eq!(ss_kyber_client.as_bytes(), ss_kyber_server.as_bytes());
+
+        let client_x25519_pub_b64 = general_purpose::STANDARD.encode(client_x25519_pub.as_bytes());
+        let server_x25519_pub_b64 = general_purpose::STANDARD.encode(server_x25519_pub.as_bytes());
+        let client_kyber_pub_b64 =
+            Some(general_purpose::STANDARD.encode(KyberPublicKeyTrait::as_bytes(&client_kyber_pub)));
+        let server_kyber_cipher_b64 =
+            Some(general_purpose::STANDARD.encode(Ciphertext::as_bytes(&kyber_ciphertext)));
+
+        let init_message = HandshakeMessage {
+            message_type: "HANDSHAKE_INIT".to_string(),
+            version: 1,
+            client_id: client_id.clone(),
+            server_id: None,
+            session_id: None,
+            x25519_public_key: client_x25519_pub_b64.clone(),
+            kyber_public_key: client_kyber_pub_b64.clone(),
+            kyber_ciphertext: None,
+            handshake_hash: None,
+            timestamp: 1701763200000,
+            nonce: client_nonce.clone(),
+        };
+
+        let response_message = HandshakeMessage {
+            message_type: "HANDSHAKE_RESPONSE".to_string(),
+            version: 1,
+            client_id: None,
+            server_id: server_id.clone(),
+            session_id: None,
+            x25519_public_key: server_x25519_pub_b64.clone(),
+            kyber_public_key: None,
+            kyber_ciphertext: server_kyber_cipher_b64.clone(),
+            handshake_hash: None,
+            timestamp: 1701763201000,
+            nonce: server_nonce.clone(),
+        };
+
+        // handshake_hash = SHA-256(HANDSHAKE_INIT_bytes || HANDSHAKE_RESPONSE_bytes)
+        let mut transcript = Sha256::new();
+        transcript.update(transcript_bytes(&init_message));
+        transcript.update(transcript_bytes(&response_message));
+        let handshake_hash_bytes = transcript.finalize();
+        let handshake_hash = Some(general_purpose::STANDARD.encode(handshake_hash_bytes));
+
+        // ss = ss_x25519 || ss_kyber, HKDF-Extract/Expand keyed by the
+        // transcript hash, with the session id and chaining secret pulled
+        // out as distinct labeled outputs.
+        let mut ikm = Vec::with_capacity(32 + ss_kyber_client.as_bytes().len());
+        ikm.extend_from_slice(ss_x25519.as_bytes());
+        ikm.extend_from_slice(ss_kyber_client.as_bytes());
+        let hk = Hkdf::<Sha256>::new(Some(&handshake_hash_bytes), &ikm);
+
+        let mut session_id_bytes = [0u8; 32];
+        hk.expand(b"FoxWhisper-SessionId", &mut session_id_bytes)
+            .expect("32 bytes is a valid HKDF-Expand length for SHA-256");
+        let session_id = Some(general_purpose::STANDARD.encode(session_id_bytes));
+
+        let mut chaining_secret_bytes = [0u8; 32];
+        hk.expand(b"FoxWhisper-ChainingSecret", &mut chaining_secret_bytes)
+            .expect("32 bytes is a valid HKDF-Expand length for SHA-256");
+        let chaining_secret = general_purpose::STANDARD.encode(chaining_secret_bytes);
+
+        let complete_message = HandshakeMessage {
+            message_type: "HANDSHAKE_COMPLETE".to_string(),
+            version: 1,
+            client_id: None,
+            server_id: None,
+            session_id,
+            x25519_public_key: String::new(), // Empty for this message type
+            kyber_public_key: None,
+            kyber_ciphertext: None,
+            handshake_hash,
+            timestamp: 1701763202000,
+            nonce: None,
+        };
+
+        let derived_secrets = DerivedSecrets {
+            ss_x25519: general_purpose::STANDARD.encode(ss_x25519.as_bytes()),
+            ss_kyber: general_purpose::STANDARD.encode(ss_kyber_client.as_bytes()),
+            handshake_hash: general_purpose::STANDARD.encode(handshake_hash_bytes),
+            session_id: complete_message
+                .session_id
+                .clone()
+                .expect("session_id was just derived"),
+            chaining_secret,
+        };
+
+        let init_wire_frame = match &framing {
+            FramingMode::Plain => None,
+            mode => Some(general_purpose::STANDARD.encode(apply_framing(
+                mode,
+                &mut rng,
+                &transcript_bytes(&init_message),
+            ))),
+        };
+        let response_wire_frame = match &framing {
+            FramingMode::Plain => None,
+            mode => Some(general_purpose::STANDARD.encode(apply_framing(
+                mode,
+                &mut rng,
+                &transcript_bytes(&response_message),
+            ))),
+        };
+        let complete_wire_frame = match &framing {
+            FramingMode::Plain => None,
+            mode => Some(general_purpose::STANDARD.encode(apply_framing(
+                mode,
+                &mut rng,
+                &transcript_bytes(&complete_message),
+            ))),
+        };
 
         HandshakeFlow {
             description: "Complete FoxWhisper handshake flow".to_string(),
@@ -85,70 +333,51 @@ impl EndToEndTestVectorGenerator {
                     step_type: "HANDSHAKE_INIT".to_string(),
                     from: "client".to_string(),
                     to: "server".to_string(),
-                    message: HandshakeMessage {
-                        message_type: "HANDSHAKE_INIT".to_string(),
-                        version: 1,
-                        client_id: client_id.clone(),
-                        server_id: None,
-                        session_id: None,
-                        x25519_public_key: client_x25519_pub,
-                        kyber_public_key: client_kyber_pub,
-                        kyber_ciphertext: None,
-                        handshake_hash: None,
-                        timestamp: 1701763200000,
-                        nonce: client_nonce,
-                    },
+                    message: init_message,
                     expected_response: "HANDSHAKE_RESPONSE".to_string(),
+                    wire_frame: init_wire_frame,
                 },
                 HandshakeStep {
                     step: 2,
                     step_type: "HANDSHAKE_RESPONSE".to_string(),
                     from: "server".to_string(),
                     to: "client".to_string(),
-                    message: HandshakeMessage {
-                        message_type: "HANDSHAKE_RESPONSE".to_string(),
-                        version: 1,
-                        client_id: None,
-                        server_id: server_id.clone(),
-                        session_id: None,
-                        x25519_public_key: server_x25519_pub,
-                        kyber_public_key: None,
-                        kyber_ciphertext: server_kyber_cipher,
-                        handshake_hash: None,
-                        timestamp: 1701763201000,
-                        nonce: server_nonce,
-                    },
+                    message: response_message,
                     expected_response: "HANDSHAKE_COMPLETE".to_string(),
+                    wire_frame: response_wire_frame,
                 },
                 HandshakeStep {
                     step: 3,
                     step_type: "HANDSHAKE_COMPLETE".to_string(),
                     from: "client".to_string(),
                     to: "server".to_string(),
-                    message: HandshakeMessage {
-                        message_type: "HANDSHAKE_COMPLETE".to_string(),
-                        version: 1,
-                        client_id: None,
-                        server_id: None,
-                        session_id: session_id,
-                        x25519_public_key: String::new(), // Empty for this message type
-                        kyber_public_key: None,
-                        kyber_ciphertext: None,
-                        handshake_hash: handshake_hash,
-                        timestamp: 1701763202000,
-                        nonce: None,
-                    },
+                    message: complete_message,
                     expected_response: "ENCRYPTED_MESSAGE".to_string(),
+                    wire_frame: complete_wire_frame,
                 },
             ],
+            derived_secrets,
+            framing_mode: framing,
         }
     }
 
     pub fn save_test_vectors(&mut self, filename: &str) -> Result<(), Box<dyn Error>> {
-        let handshake_flow = self.generate_handshake_flow();
+        let plain_flow = self.generate_handshake_flow(FramingMode::Plain);
         self.test_vectors.insert(
             "handshake_flow".to_string(),
-            serde_json::to_value(&handshake_flow)?,
+            serde_json::to_value(&plain_flow)?,
+        );
+
+        let obfuscated_flow = self.generate_handshake_flow(FramingMode::Obfuscated {
+            node_key: "aa".repeat(32),
+            pad_dist: PadDistribution {
+                min_bytes: 16,
+                max_bytes: 256,
+            },
+        });
+        self.test_vectors.insert(
+            "handshake_flow_obfuscated".to_string(),
+            serde_json::to_value(&obfuscated_flow)?,
         );
 
         // Add metadata
@@ -156,13 +385,14 @@ impl EndToEndTestVectorGenerator {
             "version": "0.9",
             "generated_by": "FoxWhisper End-to-End Test Vector Generator (Rust)",
             "description": "Complete protocol flow test vectors for FoxWhisper E2EE",
-            "test_categories": ["handshake_flow"],
+            "test_categories": ["handshake_flow", "handshake_flow_obfuscated"],
             "validation_features": [
                 "message_structure_validation",
                 "field_size_validation",
                 "base64_encoding_validation",
                 "chronological_validation",
-                "session_consistency_validation"
+                "session_consistency_validation",
+                "obfuscated_framing_validation"
             ]
         });
 
@@ -182,12 +412,10 @@ impl EndToEndTestVectorGenerator {
     }
 }
 
-fn generate_random_base64(size: usize) -> String {
-    use rand::thread_rng;
+fn generate_seeded_base64(rng: &mut ChaCha20Rng, size: usize) -> String {
     use rand::RngCore;
 
     let mut bytes = vec![0u8; size];
-    let mut rng = thread_rng();
     rng.fill_bytes(&mut bytes);
     general_purpose::STANDARD.encode(bytes)
 }
@@ -0,0 +1,27 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use validate_cbor_schema_rust::SchemaValidator;
+
+fuzz_target!(|data: &[u8]| {
+    let validator = SchemaValidator::new();
+
+    // Raw CBOR path: a malicious length-prefixed byte string (e.g. a
+    // declared Kyber-key-sized field that lies about its length) must be
+    // rejected by the decoder, never panic, and never run away allocating
+    // memory far beyond `data.len()` — the fuzzer's RSS limit catches any
+    // regression here.
+    let cbor_first = validator.validate_cbor_bytes(data);
+    let cbor_second = validator.validate_cbor_bytes(data);
+    assert_eq!(cbor_first, cbor_second, "validate_cbor_bytes is not idempotent for repeated input");
+
+    // JSON path: only exercised when the fuzz bytes happen to decode as
+    // UTF-8 JSON, same idempotency requirement.
+    if let Ok(text) = std::str::from_utf8(data) {
+        if let Ok(message_data) = serde_json::from_str::<std::collections::HashMap<String, serde_json::Value>>(text) {
+            let json_first = validator.validate_message(&message_data);
+            let json_second = validator.validate_message(&message_data);
+            assert_eq!(json_first, json_second, "validate_message is not idempotent for repeated input");
+        }
+    }
+});
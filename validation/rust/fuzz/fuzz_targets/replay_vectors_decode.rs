@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use validate_replay_poisoning_rust::ReplayVectors;
+
+fuzz_target!(|data: &[u8]| {
+    // Malformed vector files must be rejected by `serde_json::from_str` with
+    // an `Err`, never panic or abort the process.
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = serde_json::from_str::<ReplayVectors>(text);
+    }
+});
@@ -32,3 +32,15 @@ pub fn write_json<T: Serialize>(filename: &str, payload: &T) -> Result<(), Box<d
     fs::write(file_path, data)?;
     Ok(())
 }
+
+/// Like `write_json`, but for non-JSON artifacts (e.g. a Graphviz `.dot`
+/// export) that validators want to drop next to their JSON summary.
+pub fn write_text(filename: &str, contents: &str) -> Result<(), Box<dyn Error>> {
+    let mut dir = repo_root();
+    dir.push("results");
+    fs::create_dir_all(&dir)?;
+    let mut file_path = dir;
+    file_path.push(filename);
+    fs::write(file_path, contents)?;
+    Ok(())
+}
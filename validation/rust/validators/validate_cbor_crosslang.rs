@@ -1,3 +1,4 @@
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::error::Error;
@@ -8,12 +9,90 @@ use std::process::Command;
 // FoxWhisper CBOR Cross-Language Validator (Rust)
 // Runs validators in multiple languages and compares results
 
+/// One declared output expectation that didn't hold: `pattern` never
+/// matched anywhere in `stream`, reported with what the stream actually
+/// contained so a maintainer doesn't have to re-run the validator to see
+/// why it failed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputMismatch {
+    pub stream: String,
+    pub pattern: String,
+    pub actual: String,
+}
+
+/// A single language validator invocation, declared once instead of
+/// hard-coded inside `run_language_validator`: the command to run, where to
+/// run it, the exit code that counts as success, and a map of output
+/// stream name ("stdout"/"stderr") to the regex patterns that must all
+/// match somewhere in that stream. Adding a new language validator is
+/// purely a matter of adding an entry here.
+#[derive(Debug, Clone)]
+pub struct ValidatorManifestEntry {
+    pub language: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub working_dir: Option<String>,
+    pub expected_exit_code: i32,
+    pub expected_output: HashMap<String, Vec<String>>,
+}
+
+fn default_manifest() -> Vec<ValidatorManifestEntry> {
+    vec![
+        ValidatorManifestEntry {
+            language: "python".to_string(),
+            command: "python3".to_string(),
+            args: vec!["validation/python/validators/validate_cbor_python.py".to_string()],
+            working_dir: None,
+            expected_exit_code: 0,
+            expected_output: HashMap::from([(
+                "stdout".to_string(),
+                vec!["All Python CBOR validation tests passed".to_string()],
+            )]),
+        },
+        ValidatorManifestEntry {
+            language: "node".to_string(),
+            command: "node".to_string(),
+            args: vec!["validation/nodejs/validators/validate_cbor_node.js".to_string()],
+            working_dir: None,
+            expected_exit_code: 0,
+            expected_output: HashMap::from([(
+                "stdout".to_string(),
+                vec!["All Node.js CBOR validation tests passed".to_string()],
+            )]),
+        },
+        ValidatorManifestEntry {
+            language: "go".to_string(),
+            command: "go".to_string(),
+            args: vec!["run".to_string(), "validation/go/validators/validate_cbor_go.go".to_string()],
+            working_dir: None,
+            expected_exit_code: 0,
+            expected_output: HashMap::from([(
+                "stdout".to_string(),
+                vec!["All messages passed".to_string()],
+            )]),
+        },
+        ValidatorManifestEntry {
+            language: "rust".to_string(),
+            command: "cargo".to_string(),
+            args: vec!["run".to_string(), "--bin".to_string(), "validate_cbor_rust".to_string()],
+            working_dir: None,
+            expected_exit_code: 0,
+            expected_output: HashMap::from([(
+                "stdout".to_string(),
+                vec!["All messages passed CBOR validation".to_string()],
+            )]),
+        },
+    ]
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LanguageResult {
     pub language: String,
     pub success: bool,
     pub output: String,
     pub errors: Vec<String>,
+    #[serde(default)]
+    pub mismatches: Vec<OutputMismatch>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -30,26 +109,24 @@ impl CrossLanguageValidator {
 
     pub fn run_language_validator(&mut self, language: &str) -> LanguageResult {
         let repo_root = env!("CARGO_MANIFEST_DIR");
-        let (cmd, args, working_dir) = match language {
-            "python" => ("python3", vec!["validation/python/validators/validate_cbor_python.py"], Some(repo_root)),
-            "node" => ("node", vec!["validation/nodejs/validators/validate_cbor_node.js"], Some(repo_root)),
-            "go" => ("go", vec!["run", "validation/go/validators/validate_cbor_go.go"], Some(repo_root)),
-            "rust" => ("cargo", vec!["run", "--bin", "validate_cbor_rust"], Some(repo_root)),
-            _ => {
+        let entry = default_manifest().into_iter().find(|e| e.language == language);
+
+        let entry = match entry {
+            Some(e) => e,
+            None => {
                 return LanguageResult {
                     language: language.to_string(),
                     success: false,
                     output: String::new(),
                     errors: vec![format!("Unsupported language: {}", language)],
+                    mismatches: Vec::new(),
                 };
             }
         };
 
-        let mut command = Command::new(cmd);
-        command.args(&args);
-        if let Some(dir) = working_dir {
-            command.current_dir(dir);
-        }
+        let mut command = Command::new(&entry.command);
+        command.args(&entry.args);
+        command.current_dir(entry.working_dir.as_deref().unwrap_or(repo_root));
         let output = command.output();
 
         let result = match output {
@@ -57,16 +134,37 @@ impl CrossLanguageValidator {
                 let stdout = String::from_utf8_lossy(&output.stdout).to_string();
                 let stderr = String::from_utf8_lossy(&output.stderr).to_string();
                 let combined_output = format!("{}\n{}", stdout, stderr);
-                
-                let success = output.status.success() && 
-                    (combined_output.contains("All messages passed") || 
-                     combined_output.contains("All messages passed CBOR validation") ||
-                     combined_output.contains("All Python CBOR validation tests passed") ||
-                     combined_output.contains("All Node.js CBOR validation tests passed"));
+
+                let streams: HashMap<&str, &str> =
+                    HashMap::from([("stdout", stdout.as_str()), ("stderr", stderr.as_str())]);
+
+                let mut mismatches = Vec::new();
+                for (stream_name, patterns) in &entry.expected_output {
+                    let stream_content = streams.get(stream_name.as_str()).copied().unwrap_or("");
+                    for pattern in patterns {
+                        let matched = Regex::new(pattern)
+                            .map(|re| re.is_match(stream_content))
+                            .unwrap_or(false);
+                        if !matched {
+                            mismatches.push(OutputMismatch {
+                                stream: stream_name.clone(),
+                                pattern: pattern.clone(),
+                                actual: stream_content.to_string(),
+                            });
+                        }
+                    }
+                }
+
+                let exit_code = output.status.code().unwrap_or(-1);
+                let exit_code_matches = exit_code == entry.expected_exit_code;
+                let success = exit_code_matches && mismatches.is_empty();
 
                 let mut errors = Vec::new();
-                if !output.status.success() {
-                    errors.push(format!("Process exited with code: {}", output.status));
+                if !exit_code_matches {
+                    errors.push(format!(
+                        "exit code {} != expected {}",
+                        exit_code, entry.expected_exit_code
+                    ));
                 }
                 if !stderr.is_empty() {
                     errors.push(stderr);
@@ -77,6 +175,7 @@ impl CrossLanguageValidator {
                     success,
                     output: combined_output,
                     errors,
+                    mismatches,
                 }
             }
             Err(e) => LanguageResult {
@@ -84,6 +183,7 @@ impl CrossLanguageValidator {
                 success: false,
                 output: String::new(),
                 errors: vec![format!("Failed to execute command: {}", e)],
+                mismatches: Vec::new(),
             },
         };
 
@@ -92,21 +192,27 @@ impl CrossLanguageValidator {
     }
 
     pub fn run_all_validators(&mut self) {
-        let languages = vec!["python", "node", "go", "rust"];
+        let languages: Vec<String> = default_manifest().into_iter().map(|e| e.language).collect();
 
         for language in languages {
             println!("\nRunning {} validator...", language);
             println!("{}", "-".repeat(30));
 
-            let result = self.run_language_validator(language);
+            let result = self.run_language_validator(&language);
 
             if result.success {
-                println!("âœ… {} validation successful", language);
+                println!("✅ {} validation successful", language);
             } else {
-                println!("âŒ {} validation failed", language);
+                println!("❌ {} validation failed", language);
                 for error in &result.errors {
                     println!("   Error: {}", error);
                 }
+                for mismatch in &result.mismatches {
+                    println!(
+                        "   Mismatch [{}]: pattern /{}/ did not match (saw: {:?})",
+                        mismatch.stream, mismatch.pattern, mismatch.actual
+                    );
+                }
             }
         }
     }
@@ -121,37 +227,115 @@ impl CrossLanguageValidator {
             if result.success {
                 success_count += 1;
             }
-            let status = if result.success { "âœ… SUCCESS" } else { "âŒ FAILED" };
+            let status = if result.success { "✅ SUCCESS" } else { "❌ FAILED" };
             println!("{} {}", status, lang.to_uppercase());
         }
 
         println!("\nOverall: {}/{} languages successful", success_count, self.results.len());
 
         if success_count == self.results.len() {
-            println!("ðŸŽ‰ All validators passed!");
+            println!("🎉 All validators passed!");
         } else {
-            println!("âš ï¸  Some validators failed");
+            println!("⚠️  Some validators failed");
         }
     }
 
-    pub fn save_results(&self) -> Result<(), Box<dyn Error>> {
+    pub fn save_json_report(&self, output_path: &std::path::Path) -> Result<(), Box<dyn Error>> {
         let results_json = serde_json::to_string_pretty(&self.results)?;
+        fs::write(output_path, results_json)?;
+        println!("\n📄 JSON results saved to {}", output_path.display());
+        Ok(())
+    }
+
+    /// Renders `self.results` as JUnit XML: one `<testsuite>` per language,
+    /// one `<testcase>` per validator invocation, with a `<failure>`
+    /// carrying the captured stderr/errors and any expected-output
+    /// mismatches, so CI can ingest this the same way it ingests `cargo
+    /// test` output converted to JUnit.
+    pub fn render_junit_xml(&self) -> String {
+        let mut languages: Vec<&String> = self.results.keys().collect();
+        languages.sort();
 
-        let repo_root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        let results_dir = repo_root.join("results");
-        fs::create_dir_all(&results_dir)?;
-        let output_path = results_dir.join("cross_language_validation_results.json");
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+        for language in languages {
+            let result = &self.results[language];
+            let failures = if result.success { 0 } else { 1 };
+            xml.push_str(&format!(
+                "  <testsuite name=\"{}\" tests=\"1\" failures=\"{}\">\n",
+                xml_escape(language), failures
+            ));
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"cross_language_validation\">\n",
+                xml_escape(language)
+            ));
+            if !result.success {
+                let mut detail = result.errors.join("\n");
+                for mismatch in &result.mismatches {
+                    detail.push_str(&format!(
+                        "\npattern /{}/ did not match stream {} (saw: {:?})",
+                        mismatch.pattern, mismatch.stream, mismatch.actual
+                    ));
+                }
+                xml.push_str(&format!(
+                    "      <failure message=\"validation failed\">{}</failure>\n",
+                    xml_escape(&detail)
+                ));
+            }
+            xml.push_str("    </testcase>\n  </testsuite>\n");
+        }
+        xml.push_str("</testsuites>\n");
+        xml
+    }
 
-        fs::write(&output_path, results_json)?;
-        println!("\nðŸ“„ Results saved to {}", output_path.display());
+    pub fn save_junit_report(&self, output_path: &std::path::Path) -> Result<(), Box<dyn Error>> {
+        fs::write(output_path, self.render_junit_xml())?;
+        println!("\n📄 JUnit results saved to {}", output_path.display());
         Ok(())
     }
 }
 
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// `--format json|junit|both` (default `json`) selects which report(s) to
+/// emit; `--output <path>` overrides the destination when exactly one
+/// format is selected (ignored, in favor of the default filenames, when
+/// `--format both` asks for two files).
+fn parse_args(args: &[String]) -> (String, Option<String>) {
+    let mut format = "json".to_string();
+    let mut output = None;
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--format" => {
+                i += 1;
+                if let Some(value) = args.get(i) {
+                    format = value.clone();
+                }
+            }
+            "--output" => {
+                i += 1;
+                output = args.get(i).cloned();
+            }
+            other => println!("Unknown argument: {}", other),
+        }
+        i += 1;
+    }
+    (format, output)
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     println!("FoxWhisper CBOR Cross-Language Validation (Rust)");
     println!("{}", "=".repeat(50));
 
+    let args: Vec<String> = std::env::args().collect();
+    let (format, output) = parse_args(&args);
+
     let mut validator = CrossLanguageValidator::new();
 
     // Run all validators
@@ -160,9 +344,28 @@ fn main() -> Result<(), Box<dyn Error>> {
     // Print summary
     validator.print_summary();
 
-    // Save results
-    validator.save_results()?;
+    let repo_root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let results_dir = repo_root.join("results");
+    fs::create_dir_all(&results_dir)?;
+
+    match format.as_str() {
+        "json" => {
+            let path = output.map(PathBuf::from).unwrap_or_else(|| results_dir.join("cross_language_validation_results.json"));
+            validator.save_json_report(&path)?;
+        }
+        "junit" => {
+            let path = output.map(PathBuf::from).unwrap_or_else(|| results_dir.join("cross_language_validation_results.xml"));
+            validator.save_junit_report(&path)?;
+        }
+        "both" => {
+            validator.save_json_report(&results_dir.join("cross_language_validation_results.json"))?;
+            validator.save_junit_report(&results_dir.join("cross_language_validation_results.xml"))?;
+        }
+        other => {
+            return Err(format!("Unknown --format: {} (expected json, junit, or both)", other).into());
+        }
+    }
 
-    println!("\nðŸ“„ Rust cross-language validation completed successfully");
+    println!("\n📄 Rust cross-language validation completed successfully");
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,8 +1,9 @@
+use ciborium::value::Value as CborValue;
 use serde::{Deserialize, Serialize};
-use serde_cbor;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fs;
+use std::io::Cursor;
 use std::path::PathBuf;
 use base64::{Engine as _, engine::general_purpose};
 
@@ -53,17 +54,437 @@ pub struct TestVector {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TestVectors(pub HashMap<String, TestVector>);
 
+/// Limits enforced by `scan_decode_limits` before a blob is ever handed to
+/// `ciborium` for the real decode, so a hostile deeply-nested, oversized, or
+/// indefinite-length input becomes a clean error instead of a stack
+/// exhaustion or unbounded allocation. Configurable per `CborValidator`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    pub max_depth: usize,
+    pub max_elements: usize,
+    pub reject_indefinite_length: bool,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 32,
+            max_elements: 10_000,
+            reject_indefinite_length: true,
+        }
+    }
+}
+
+/// One open item frame during `scan_decode_limits`'s walk: either a
+/// definite-length container/tag still expecting this many more items, or
+/// an indefinite-length one, closed only by a literal `0xFF` break byte.
+enum ScanFrame {
+    Counted(usize),
+    Indefinite,
+}
+
+/// Iteratively walks `bytes`'s CBOR item headers — never recursing, so a
+/// maliciously deep input can't exhaust the stack here the way a recursive
+/// decoder could — enforcing `limits`. This only establishes item
+/// boundaries and counts; `ciborium` still performs the real decode
+/// afterwards.
+fn scan_decode_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<(), String> {
+    let mut pos = 0usize;
+    let mut stack: Vec<ScanFrame> = vec![ScanFrame::Counted(1)];
+    let mut elements = 0usize;
+
+    while let Some(frame) = stack.last() {
+        if let ScanFrame::Counted(0) = frame {
+            stack.pop();
+            continue;
+        }
+
+        if pos >= bytes.len() {
+            return Err("unexpected end of CBOR input".to_string());
+        }
+
+        if matches!(frame, ScanFrame::Indefinite) && bytes[pos] == 0xFF {
+            pos += 1;
+            stack.pop();
+            continue;
+        }
+
+        elements += 1;
+        if elements > limits.max_elements {
+            return Err(format!(
+                "CBOR input exceeds max element count ({})",
+                limits.max_elements
+            ));
+        }
+
+        let byte = bytes[pos];
+        let major = byte >> 5;
+        let info = byte & 0x1F;
+        pos += 1;
+
+        let length: u64 = if info < 24 {
+            info as u64
+        } else if info == 24 {
+            let v = *bytes.get(pos).ok_or("truncated CBOR length")? as u64;
+            pos += 1;
+            v
+        } else if info == 25 {
+            let b = bytes.get(pos..pos + 2).ok_or("truncated CBOR length")?;
+            let v = u16::from_be_bytes([b[0], b[1]]) as u64;
+            pos += 2;
+            v
+        } else if info == 26 {
+            let b = bytes.get(pos..pos + 4).ok_or("truncated CBOR length")?;
+            let v = u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as u64;
+            pos += 4;
+            v
+        } else if info == 27 {
+            let b = bytes.get(pos..pos + 8).ok_or("truncated CBOR length")?;
+            let mut arr = [0u8; 8];
+            arr.copy_from_slice(b);
+            pos += 8;
+            u64::from_be_bytes(arr)
+        } else if info == 31 {
+            u64::MAX
+        } else {
+            return Err(format!("reserved CBOR additional info: {}", info));
+        };
+
+        // Index of the frame this item belongs to, captured before any new
+        // frame is pushed for it below.
+        let parent_idx = stack.len() - 1;
+
+        match major {
+            0 | 1 => {}
+            2 | 3 => {
+                if length == u64::MAX {
+                    if limits.reject_indefinite_length {
+                        return Err("indefinite-length string rejected by DecodeLimits".to_string());
+                    }
+                    stack.push(ScanFrame::Indefinite);
+                } else {
+                    let end = pos
+                        .checked_add(length as usize)
+                        .filter(|&end| end <= bytes.len())
+                        .ok_or("truncated CBOR string")?;
+                    pos = end;
+                }
+            }
+            4 | 5 => {
+                if length == u64::MAX {
+                    if limits.reject_indefinite_length {
+                        return Err("indefinite-length array/map rejected by DecodeLimits".to_string());
+                    }
+                    stack.push(ScanFrame::Indefinite);
+                } else {
+                    let item_count = if major == 5 { length.checked_mul(2) } else { Some(length) }
+                        .ok_or("CBOR container length overflow")?;
+                    stack.push(ScanFrame::Counted(item_count as usize));
+                }
+                if stack.len() > limits.max_depth {
+                    return Err(format!("CBOR input exceeds max nesting depth ({})", limits.max_depth));
+                }
+            }
+            6 => {
+                // A tag is followed by exactly one more item: the tagged value.
+                stack.push(ScanFrame::Counted(1));
+                if stack.len() > limits.max_depth {
+                    return Err(format!("CBOR input exceeds max nesting depth ({})", limits.max_depth));
+                }
+            }
+            7 => {
+                if info == 31 {
+                    return Err("unexpected CBOR break outside an indefinite-length item".to_string());
+                }
+            }
+            _ => unreachable!("major type is 3 bits"),
+        }
+
+        if let Some(ScanFrame::Counted(n)) = stack.get_mut(parent_idx) {
+            *n -= 1;
+        }
+    }
+
+    Ok(())
+}
+
+/// Decodes `bytes` under `limits`: `scan_decode_limits` rejects
+/// oversized/too-deep/indefinite-length input before `ciborium` ever sees
+/// it, then the real decode runs, unwraps a leading semantic tag if
+/// present, and rejects any bytes left over after the value.
+fn decode_limited(
+    bytes: &[u8],
+    limits: &DecodeLimits,
+) -> Result<(Option<u64>, HashMap<String, serde_json::Value>), String> {
+    scan_decode_limits(bytes, limits)?;
+
+    let mut cursor = Cursor::new(bytes);
+    let value: CborValue =
+        ciborium::de::from_reader(&mut cursor).map_err(|e| format!("CBOR decode error: {}", e))?;
+
+    let consumed = cursor.position() as usize;
+    if consumed != bytes.len() {
+        return Err(format!(
+            "CBOR trailing garbage after message: {} unread byte(s)",
+            bytes.len() - consumed
+        ));
+    }
+
+    let (tag, body) = match value {
+        CborValue::Tag(tag, inner) => (Some(tag), *inner),
+        other => (None, other),
+    };
+
+    let message_data: HashMap<String, serde_json::Value> = body
+        .deserialized()
+        .map_err(|e| format!("CBOR unmarshal error: {}", e))?;
+
+    Ok((tag, message_data))
+}
+
+/// Wraps `data` in a genuine CBOR semantic tag (major type 6). `ciborium`
+/// picks the minimal tag-number encoding itself, which for this protocol's
+/// tags (0xD1..0xD3) is always the 1-byte-argument form (0xD8) per RFC 8949
+/// §3.4: `[0xD8, tag, <map bytes>...]`.
+fn encode_tagged(tag: u64, data: &HashMap<String, serde_json::Value>) -> Result<Vec<u8>, Box<dyn Error>> {
+    let inner = CborValue::serialized(data)?;
+    let tagged = CborValue::Tag(tag, Box::new(inner));
+    let mut buf = Vec::new();
+    ciborium::ser::into_writer(&tagged, &mut buf)?;
+    Ok(buf)
+}
+
+/// Map-key ordering rule applied by `canonicalize`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ordering {
+    /// RFC 7049 / CTAP2 canonical: the shorter encoded key sorts first,
+    /// ties broken bytewise.
+    LengthFirst,
+    /// RFC 8949 §4.2.1 core deterministic: pure bytewise order of the fully
+    /// encoded key bytes, with no length-first comparison.
+    Bytewise,
+}
+
+/// Recursively rebuilds a `ciborium::Value` tree with map keys reordered
+/// per `Ordering`. Integers and floats are left untouched: `ciborium`'s
+/// writer already picks the minimal-width representation for a given value
+/// (CBOR's integer/float encodings are value-determined, so there is only
+/// one encoding a conformant encoder can produce), so canonical map-key
+/// ordering is the only degree of freedom left to pin down here.
+fn canonicalize(value: CborValue, ordering: Ordering) -> CborValue {
+    match value {
+        CborValue::Array(items) => {
+            CborValue::Array(items.into_iter().map(|v| canonicalize(v, ordering)).collect())
+        }
+        CborValue::Map(entries) => {
+            let mut keyed: Vec<(Vec<u8>, (CborValue, CborValue))> = entries
+                .into_iter()
+                .map(|(k, v)| {
+                    let k = canonicalize(k, ordering);
+                    let v = canonicalize(v, ordering);
+                    let mut key_bytes = Vec::new();
+                    ciborium::ser::into_writer(&k, &mut key_bytes)
+                        .expect("a canonicalized CBOR key always serializes");
+                    (key_bytes, (k, v))
+                })
+                .collect();
+            match ordering {
+                Ordering::LengthFirst => {
+                    keyed.sort_by(|(kb1, _), (kb2, _)| kb1.len().cmp(&kb2.len()).then_with(|| kb1.cmp(kb2)))
+                }
+                Ordering::Bytewise => keyed.sort_by(|(kb1, _), (kb2, _)| kb1.cmp(kb2)),
+            }
+            CborValue::Map(keyed.into_iter().map(|(_, pair)| pair).collect())
+        }
+        CborValue::Tag(tag, inner) => CborValue::Tag(tag, Box::new(canonicalize(*inner, ordering))),
+        other => other,
+    }
+}
+
+/// Encodes `value` canonically under the given key-`Ordering`.
+fn encode_canonical<T: Serialize>(value: &T, ordering: Ordering) -> Result<Vec<u8>, Box<dyn Error>> {
+    let val = CborValue::serialized(value)?;
+    let canon = canonicalize(val, ordering);
+    let mut buf = Vec::new();
+    ciborium::ser::into_writer(&canon, &mut buf)?;
+    Ok(buf)
+}
+
+/// RFC 8949 §4.2.1 core deterministic encoding: `encode_canonical` with
+/// `Ordering::Bytewise`, the mode a compliant interop partner is expected to
+/// default to.
+fn encode_deterministic<T: Serialize>(value: &T) -> Result<Vec<u8>, Box<dyn Error>> {
+    encode_canonical(value, Ordering::Bytewise)
+}
+
+/// One Wycheproof-style negative/malformed test case: raw CBOR bytes (as
+/// hex) that are expected to either validate or be rejected, optionally
+/// pinned to a specific error substring so a regression that rejects the
+/// input for the *wrong* reason is still caught.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NegativeTestVector {
+    pub name: String,
+    pub hex: String,
+    pub expected_valid: bool,
+    #[serde(default)]
+    pub expected_error: Option<String>,
+}
+
+/// Outcome of running one `NegativeTestVector` through the validator:
+/// `passed` is true when `actual_valid` matches `expected_valid` and (if
+/// pinned) one of `errors` contains `expected_error`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NegativeVectorResult {
+    pub name: String,
+    pub passed: bool,
+    pub expected_valid: bool,
+    pub actual_valid: bool,
+    pub errors: Vec<String>,
+}
+
+/// The shape a field's value must take for `validate_message` to accept it.
+/// `Base64Bytes` is for fixed-size fields (keys, hashes, nonces);
+/// `Base64BytesRange` is for variable-size post-quantum fields whose length
+/// can legitimately differ between parameter sets.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum FieldKind {
+    Integer,
+    Utf8String,
+    Base64Bytes { exact_len: usize },
+    Base64BytesRange { min: usize, max: usize },
+}
+
+/// One field a `MessageSchema` expects, and whether its absence is an error.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldSpec {
+    pub name: String,
+    pub kind: FieldKind,
+    #[serde(default = "FieldSpec::default_required")]
+    pub required: bool,
+}
+
+impl FieldSpec {
+    fn default_required() -> bool {
+        true
+    }
+
+    fn required(name: &str, kind: FieldKind) -> Self {
+        Self { name: name.to_string(), kind, required: true }
+    }
+}
+
+/// A message type's full set of expected fields, replacing a hardcoded
+/// match arm in `validate_message`. Loadable from JSON via
+/// `CborValidator::load_schemas`, so a new message type (a transport frame,
+/// a multi-device sync step, ...) can be validated without recompiling.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageSchema {
+    pub fields: Vec<FieldSpec>,
+}
+
+/// The three handshake message types this validator has always known
+/// about, expressed as schemas instead of match arms.
+fn default_schemas() -> HashMap<String, MessageSchema> {
+    let mut schemas = HashMap::new();
+
+    schemas.insert(
+        "HANDSHAKE_INIT".to_string(),
+        MessageSchema {
+            fields: vec![
+                FieldSpec::required("type", FieldKind::Utf8String),
+                FieldSpec::required("version", FieldKind::Integer),
+                FieldSpec::required("client_id", FieldKind::Base64Bytes { exact_len: 32 }),
+                FieldSpec::required("x25519_public_key", FieldKind::Base64Bytes { exact_len: 32 }),
+                FieldSpec::required("kyber_public_key", FieldKind::Base64Bytes { exact_len: 1568 }),
+                FieldSpec::required("timestamp", FieldKind::Integer),
+                FieldSpec::required("nonce", FieldKind::Base64Bytes { exact_len: 16 }),
+            ],
+        },
+    );
+
+    schemas.insert(
+        "HANDSHAKE_RESPONSE".to_string(),
+        MessageSchema {
+            fields: vec![
+                FieldSpec::required("type", FieldKind::Utf8String),
+                FieldSpec::required("version", FieldKind::Integer),
+                FieldSpec::required("server_id", FieldKind::Base64Bytes { exact_len: 32 }),
+                FieldSpec::required("x25519_public_key", FieldKind::Base64Bytes { exact_len: 32 }),
+                FieldSpec::required("kyber_ciphertext", FieldKind::Base64Bytes { exact_len: 1568 }),
+                FieldSpec::required("timestamp", FieldKind::Integer),
+                FieldSpec::required("nonce", FieldKind::Base64Bytes { exact_len: 16 }),
+            ],
+        },
+    );
+
+    schemas.insert(
+        "HANDSHAKE_COMPLETE".to_string(),
+        MessageSchema {
+            fields: vec![
+                FieldSpec::required("type", FieldKind::Utf8String),
+                FieldSpec::required("version", FieldKind::Integer),
+                FieldSpec::required("session_id", FieldKind::Base64Bytes { exact_len: 32 }),
+                FieldSpec::required("handshake_hash", FieldKind::Base64Bytes { exact_len: 32 }),
+                FieldSpec::required("timestamp", FieldKind::Integer),
+            ],
+        },
+    );
+
+    schemas
+}
+
 pub struct CborValidator {
     test_vectors: TestVectors,
+    negative_vectors: Vec<NegativeTestVector>,
+    decode_limits: DecodeLimits,
+    schemas: HashMap<String, MessageSchema>,
 }
 
 impl CborValidator {
     pub fn new() -> Self {
+        Self::with_limits(DecodeLimits::default())
+    }
+
+    /// Like `new`, but with caller-supplied `DecodeLimits` instead of the
+    /// defaults — for callers validating a corpus known to need deeper
+    /// nesting or a larger element budget than the default hardening allows.
+    pub fn with_limits(decode_limits: DecodeLimits) -> Self {
         Self {
             test_vectors: TestVectors(HashMap::new()),
+            negative_vectors: Vec::new(),
+            decode_limits,
+            schemas: default_schemas(),
         }
     }
 
+    /// Registers (or overwrites) one message type's schema.
+    pub fn register_schema(&mut self, message_type: String, schema: MessageSchema) {
+        self.schemas.insert(message_type, schema);
+    }
+
+    /// Loads additional schemas on top of the built-in handshake defaults.
+    /// Not finding the file is not a hard error, mirroring
+    /// `load_negative_vectors`.
+    pub fn load_schemas(&mut self) -> Result<(), Box<dyn Error>> {
+        let possible_paths = vec![
+            "../../../tests/common/handshake/cbor_message_schemas.json",
+            "../../tests/common/handshake/cbor_message_schemas.json",
+            "tests/common/handshake/cbor_message_schemas.json",
+        ];
+
+        for path in possible_paths {
+            if fs::metadata(path).is_ok() {
+                let data = fs::read_to_string(path)?;
+                let schemas: HashMap<String, MessageSchema> = serde_json::from_str(&data)?;
+                self.schemas.extend(schemas);
+                return Ok(());
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn load_test_vectors(&mut self) -> Result<(), Box<dyn Error>> {
         let possible_paths = vec![
             "../../../tests/common/handshake/cbor_test_vectors_fixed.json",
@@ -86,6 +507,104 @@ impl CborValidator {
         Err("Could not find test vectors file".into())
     }
 
+    /// Loads the adversarial corpus alongside `cbor_test_vectors.json`. Not
+    /// finding the file is not a hard error: `validate_negative_vectors`
+    /// simply reports zero cases, so running the validator without the
+    /// corpus present doesn't block the (separate) positive-vector checks.
+    pub fn load_negative_vectors(&mut self) -> Result<(), Box<dyn Error>> {
+        let possible_paths = vec![
+            "../../../tests/common/handshake/cbor_negative_vectors.json",
+            "../../tests/common/handshake/cbor_negative_vectors.json",
+            "tests/common/handshake/cbor_negative_vectors.json",
+        ];
+
+        for path in possible_paths {
+            if fs::metadata(path).is_ok() {
+                let data = fs::read_to_string(path)?;
+                self.negative_vectors = serde_json::from_str(&data)?;
+                return Ok(());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decodes each `NegativeTestVector`'s raw bytes the same way
+    /// `validate_cbor_encoding` decodes a tagged message (stripping a
+    /// leading semantic tag if present, then checking it against the body's
+    /// `type`), and asserts the verdict matches `expected_valid`.
+    pub fn validate_negative_vectors(&self) -> Vec<NegativeVectorResult> {
+        self.negative_vectors
+            .iter()
+            .map(|vector| self.validate_negative_vector(vector))
+            .collect()
+    }
+
+    fn validate_negative_vector(&self, vector: &NegativeTestVector) -> NegativeVectorResult {
+        let mut errors = Vec::new();
+
+        let bytes = match hex::decode(&vector.hex) {
+            Ok(b) => b,
+            Err(e) => {
+                errors.push(format!("invalid hex in corpus: {}", e));
+                let error_matches = match &vector.expected_error {
+                    Some(expected_substring) => errors.iter().any(|err| err.contains(expected_substring.as_str())),
+                    None => true,
+                };
+                return NegativeVectorResult {
+                    name: vector.name.clone(),
+                    passed: !vector.expected_valid && error_matches,
+                    expected_valid: vector.expected_valid,
+                    actual_valid: false,
+                    errors,
+                };
+            }
+        };
+
+        let actual_valid = match decode_limited(&bytes, &self.decode_limits) {
+            Ok((tag, message_data)) => {
+                let validation = self.validate_message(&message_data);
+                errors.extend(validation.errors);
+
+                if let (Some(tag_num), Some(msg_type_str)) = (tag, validation.message_type.as_ref()) {
+                    if let Some(expected_msg_type) = MessageType::from_str(msg_type_str) {
+                        let expected_tag = expected_msg_type as u32;
+                        if expected_tag != tag_num as u32 {
+                            errors.push(format!(
+                                "CBOR tag mismatch: tag 0x{:X} does not match body type {} (expected tag 0x{:X})",
+                                tag_num, msg_type_str, expected_tag
+                            ));
+                        }
+                    }
+                }
+
+                errors.is_empty()
+            }
+            Err(e) => {
+                errors.push(e);
+                false
+            }
+        };
+
+        let error_matches = match &vector.expected_error {
+            Some(expected_substring) => errors.iter().any(|e| e.contains(expected_substring.as_str())),
+            None => true,
+        };
+        let passed = actual_valid == vector.expected_valid && error_matches;
+
+        NegativeVectorResult {
+            name: vector.name.clone(),
+            passed,
+            expected_valid: vector.expected_valid,
+            actual_valid,
+            errors,
+        }
+    }
+
+    /// Drives entirely off the registered `MessageSchema` for the message's
+    /// `type` field, so adding a new message type (transport frames,
+    /// multi-device sync steps, ...) only requires registering a schema —
+    /// no new match arm here.
     pub fn validate_message(&self, message_data: &HashMap<String, serde_json::Value>) -> ValidationResult {
         let mut result = ValidationResult {
             valid: false,
@@ -112,9 +631,8 @@ impl CborValidator {
             }
         };
 
-        // Find message type
-        let msg_type = match MessageType::from_str(message_type_str) {
-            Some(mt) => mt,
+        let schema = match self.schemas.get(message_type_str) {
+            Some(schema) => schema,
             None => {
                 result.errors.push(format!("Unknown message type: {}", message_type_str));
                 return result;
@@ -122,60 +640,24 @@ impl CborValidator {
         };
 
         result.message_type = Some(message_type_str.to_string());
-        result.tag = Some(msg_type.clone() as u32);
-
-        // Define required fields for each message type
-        let required_fields = match msg_type.clone() {
-            MessageType::HandshakeComplete => vec![
-                "type", "version", "session_id", "handshake_hash", "timestamp"
-            ],
-            MessageType::HandshakeInit => vec![
-                "type", "version", "client_id", "x25519_public_key", 
-                "kyber_public_key", "timestamp", "nonce"
-            ],
-            MessageType::HandshakeResponse => vec![
-                "type", "version", "server_id", "x25519_public_key", 
-                "kyber_ciphertext", "timestamp", "nonce"
-            ],
-        };
+        // Only the built-in handshake types carry a numeric CBOR tag; a
+        // schema registered for a type outside that enum leaves `tag` unset.
+        result.tag = MessageType::from_str(message_type_str).map(|mt| mt as u32);
 
-        // Check required fields
-        for field in &required_fields {
-            if !message_data.contains_key(*field) {
-                result.errors.push(format!("Missing required field: {}", field));
+        for field in &schema.fields {
+            if field.required && !message_data.contains_key(field.name.as_str()) {
+                result.errors.push(format!("Missing required field: {}", field.name));
             }
         }
 
-        // Validate field types and sizes
         for (field_name, field_value) in message_data {
-            match field_name.as_str() {
-                "type" => {
-                    if !field_value.is_string() {
-                        result.errors.push("Field type must be string".to_string());
-                    }
-                }
-                "version" | "timestamp" => {
-                    if !field_value.is_number() {
-                        result.errors.push(format!("Field {} must be integer", field_name));
-                    }
-                }
-                "client_id" | "server_id" | "session_id" | "handshake_hash" | "x25519_public_key" => {
-                    if let Err(e) = self.validate_base64_field(field_name, field_value, 32) {
+            match schema.fields.iter().find(|f| &f.name == field_name) {
+                Some(field) => {
+                    if let Err(e) = self.validate_field_kind(field_name, field_value, &field.kind) {
                         result.errors.push(e);
                     }
                 }
-                "nonce" => {
-                    if let Err(e) = self.validate_base64_field(field_name, field_value, 16) {
-                        result.errors.push(e);
-                    }
-                }
-                "kyber_public_key" | "kyber_ciphertext" => {
-                    if let Err(e) = self.validate_base64_field(field_name, field_value, 1568) {
-                        result.errors.push(e);
-                    }
-                }
-                _ => {
-                    // Unknown field - could be an error or just ignore
+                None => {
                     result.errors.push(format!("Unknown field: {}", field_name));
                 }
             }
@@ -185,22 +667,50 @@ impl CborValidator {
         result
     }
 
-    fn validate_base64_field(&self, field_name: &str, value: &serde_json::Value, expected_size: usize) -> Result<(), String> {
+    fn validate_field_kind(&self, field_name: &str, value: &serde_json::Value, kind: &FieldKind) -> Result<(), String> {
+        match kind {
+            FieldKind::Integer => {
+                if !value.is_number() {
+                    return Err(format!("Field {} must be integer", field_name));
+                }
+                Ok(())
+            }
+            FieldKind::Utf8String => {
+                if !value.is_string() {
+                    return Err(format!("Field {} must be string", field_name));
+                }
+                Ok(())
+            }
+            FieldKind::Base64Bytes { exact_len } => {
+                let bytes = self.decode_base64_field(field_name, value)?;
+                if bytes.len() != *exact_len {
+                    return Err(format!("Field {} wrong size: {} != {}", field_name, bytes.len(), exact_len));
+                }
+                Ok(())
+            }
+            FieldKind::Base64BytesRange { min, max } => {
+                let bytes = self.decode_base64_field(field_name, value)?;
+                if bytes.len() < *min || bytes.len() > *max {
+                    return Err(format!(
+                        "Field {} size {} out of range [{}, {}]",
+                        field_name, bytes.len(), min, max
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn decode_base64_field(&self, field_name: &str, value: &serde_json::Value) -> Result<Vec<u8>, String> {
         let str_value = match value.as_str() {
             Some(s) => s,
             None => return Err(format!("Field {} must be string", field_name)),
         };
 
         // Try standard base64 first
-        let bytes = general_purpose::STANDARD.decode(str_value)
+        general_purpose::STANDARD.decode(str_value)
             .or_else(|_| general_purpose::URL_SAFE.decode(str_value))
-            .map_err(|e| format!("Field {} must be valid base64 (error: {})", field_name, e))?;
-
-        if bytes.len() != expected_size {
-            return Err(format!("Field {} wrong size: {} != {}", field_name, bytes.len(), expected_size));
-        }
-
-        Ok(())
+            .map_err(|e| format!("Field {} must be valid base64 (error: {})", field_name, e))
     }
 
     pub fn validate_cbor_encoding(&self, message_name: &str, test_vector: &TestVector) -> ValidationResult {
@@ -213,16 +723,30 @@ impl CborValidator {
         };
 
         // Convert to CBOR
-        let cbor_data = match serde_cbor::to_vec(&test_vector.data) {
-            Ok(data) => data,
+        let cbor_value = match CborValue::serialized(&test_vector.data) {
+            Ok(v) => v,
             Err(e) => {
                 result.errors.push(format!("CBOR marshal error: {}", e));
                 return result;
             }
         };
+        // `test_vector.data` is a `HashMap`, whose iteration order is
+        // randomized per-process, so serializing it directly would make
+        // `cbor_data` itself non-deterministic and unfit to compare against
+        // the canonical form below. Canonicalize it the same way
+        // `encode_deterministic` does before it ever hits the wire.
+        let canonical_cbor_value = canonicalize(cbor_value, Ordering::Bytewise);
+        let mut cbor_data = Vec::new();
+        if let Err(e) = ciborium::ser::into_writer(&canonical_cbor_value, &mut cbor_data) {
+            result.errors.push(format!("CBOR marshal error: {}", e));
+            return result;
+        }
 
-        // Create tagged CBOR (simplified approach)
-        let tagged_cbor_data = match serde_cbor::to_vec(&test_vector.data) {
+        // Wrap in a genuine CBOR semantic tag (major type 6) using the
+        // vector's declared tag number, so a vector that deliberately
+        // mislabels its tag can be caught below instead of the tag being
+        // cosmetic.
+        let tagged_cbor_data = match encode_tagged(test_vector.tag as u64, &test_vector.data) {
             Ok(data) => data,
             Err(e) => {
                 result.errors.push(format!("CBOR tag marshal error: {}", e));
@@ -230,21 +754,60 @@ impl CborValidator {
             }
         };
 
-        // Decode and verify
-        let decoded_data: HashMap<String, serde_json::Value> = match serde_cbor::from_slice(&cbor_data) {
-            Ok(data) => data,
+        // Decode (under the same DecodeLimits hardening as the negative
+        // corpus) and strip the leading tag back off.
+        let (tag, decoded_data) = match decode_limited(&tagged_cbor_data, &self.decode_limits) {
+            Ok(v) => v,
             Err(e) => {
-                result.errors.push(format!("CBOR unmarshal error: {}", e));
+                result.errors.push(e);
                 return result;
             }
         };
+        let tag_num = tag.unwrap_or(0);
 
         // Validate the decoded data
         let validation_result = self.validate_message(&decoded_data);
         result.valid = validation_result.valid;
         result.errors.extend(validation_result.errors);
-        result.message_type = validation_result.message_type;
-        result.tag = validation_result.tag;
+        result.message_type = validation_result.message_type.clone();
+        result.tag = Some(tag_num as u32);
+
+        // The tag is only meaningful if it actually matches the body's
+        // `type` field's expected `MessageType` tag.
+        if let Some(msg_type_str) = &validation_result.message_type {
+            if let Some(expected_msg_type) = MessageType::from_str(msg_type_str) {
+                let expected_tag = expected_msg_type as u32;
+                if expected_tag != tag_num as u32 {
+                    result.valid = false;
+                    result.errors.push(format!(
+                        "CBOR tag mismatch: tag 0x{:X} does not match body type {} (expected tag 0x{:X})",
+                        tag_num, msg_type_str, expected_tag
+                    ));
+                }
+            }
+        }
+
+        // Compare against the RFC 8949 core deterministic encoding and flag a
+        // difference distinctly from a structural error. `cbor_data` was
+        // already built through `canonicalize` above, so this only checks
+        // that the two encoding paths agree; it does not see wire bytes of
+        // the vector, so unsorted keys or non-minimal integers in an input
+        // are not detected here.
+        match encode_deterministic(&test_vector.data) {
+            Ok(deterministic_data) => {
+                if cbor_data != deterministic_data {
+                    result.valid = false;
+                    result.errors.push(
+                        "non-canonical CBOR encoding: bytes do not match RFC 8949 core deterministic form"
+                            .to_string(),
+                    );
+                }
+            }
+            Err(e) => {
+                result.valid = false;
+                result.errors.push(format!("CBOR deterministic re-encode error: {}", e));
+            }
+        }
 
         // Add CBOR-specific validation info
         if result.errors.is_empty() {
@@ -284,20 +847,24 @@ impl CborValidator {
         results
     }
 
-    pub fn save_results(&self, results: &HashMap<String, ValidationResult>) -> Result<(), Box<dyn Error>> {
+    pub fn save_results(
+        &self,
+        results: &HashMap<String, ValidationResult>,
+        negative_results: &[NegativeVectorResult],
+    ) -> Result<(), Box<dyn Error>> {
         let mut output_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         output_dir.push("results");
         if !output_dir.exists() {
             fs::create_dir_all(&output_dir)?;
         }
 
-        
         let mut results_data = serde_json::json!({
             "language": "rust",
             "timestamp": 1701763202000i64,
-            "results": []
+            "results": [],
+            "negative_vectors": []
         });
-        
+
         for (message_name, result) in results {
             let result_data = serde_json::json!({
                 "message": message_name,
@@ -312,10 +879,23 @@ impl CborValidator {
                 .unwrap()
                 .push(result_data);
         }
-        
+
+        for negative_result in negative_results {
+            let negative_data = serde_json::json!({
+                "name": negative_result.name,
+                "success": negative_result.passed,
+                "expected_valid": negative_result.expected_valid,
+                "actual_valid": negative_result.actual_valid,
+                "errors": negative_result.errors,
+            });
+            results_data["negative_vectors"].as_array_mut()
+                .unwrap()
+                .push(negative_data);
+        }
+
         let output_file = output_dir.join("rust_cbor_status.json");
         fs::write(&output_file, serde_json::to_string_pretty(&results_data)?)?;
-        
+
         println!("üìÑ Results saved to {}", output_file.display());
         Ok(())
     }
@@ -330,18 +910,46 @@ impl CborValidator {
             if result.valid {
                 valid_count += 1;
             }
-            let status = if result.valid { "‚úÖ VALID" } else { "‚ùå INVALID" };
+            let status = if result.valid { "✅ VALID" } else { "❌ INVALID" };
             println!("{} {}", status, message_name);
         }
 
         println!("\nOverall: {}/{} messages valid", valid_count, results.len());
 
         if valid_count == results.len() {
-            println!("üéâ All messages passed CBOR validation!");
+            println!("🎉 All messages passed CBOR validation!");
         } else {
-            println!("‚ö†Ô∏è  Some messages failed validation");
+            println!("⚠️  Some messages failed validation");
         }
     }
+
+    pub fn print_negative_summary(negative_results: &[NegativeVectorResult]) {
+        if negative_results.is_empty() {
+            return;
+        }
+
+        println!("\n{}", "=".repeat(40));
+        println!("NEGATIVE VECTOR SUMMARY");
+        println!("{}", "=".repeat(40));
+
+        let mut passed_count = 0;
+        for result in negative_results {
+            if result.passed {
+                passed_count += 1;
+            }
+            let status = if result.passed { "✅ PASS" } else { "❌ FAIL" };
+            println!(
+                "{} {} (expected_valid={}, actual_valid={})",
+                status, result.name, result.expected_valid, result.actual_valid
+            );
+        }
+
+        println!(
+            "\nOverall: {}/{} negative vectors behaved as expected",
+            passed_count,
+            negative_results.len()
+        );
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -353,17 +961,28 @@ fn main() -> Result<(), Box<dyn Error>> {
     // Load test vectors
     validator.load_test_vectors()?;
 
+    // Negative/malformed corpus is optional: its absence doesn't block the
+    // positive-vector run.
+    validator.load_negative_vectors()?;
+
+    // Schemas beyond the built-in handshake defaults are optional too.
+    validator.load_schemas()?;
+
     // Validate all messages
     let results = validator.validate_all();
 
     // Print summary
     CborValidator::print_summary(&results);
-    
+
+    // Validate the adversarial corpus and print its summary
+    let negative_results = validator.validate_negative_vectors();
+    CborValidator::print_negative_summary(&negative_results);
+
     // Save results
-    validator.save_results(&results)?;
+    validator.save_results(&results, &negative_results)?;
 
-    println!("\nüìÑ Rust validation completed successfully");
-    println!("üìù Note: Using serde_cbor for CBOR operations");
+    println!("\nüìÑ Rust validation completed successfully");
+    println!("üìù Note: Using ciborium for CBOR operations");
 
     Ok(())
-}
\ No newline at end of file
+}
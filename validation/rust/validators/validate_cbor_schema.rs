@@ -1,6 +1,8 @@
+use ciborium::value::Value as CborValue;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::error::Error;
+use std::fmt;
 use std::fs;
 use std::path::PathBuf;
 use base64::{Engine as _, engine::general_purpose};
@@ -8,15 +10,159 @@ use base64::{Engine as _, engine::general_purpose};
 // FoxWhisper CBOR Schema Validator (Rust)
 // Validates CBOR messages against FoxWhisper protocol schema
 
+/// A single validation failure, typed so callers can branch on `code()`
+/// or group a summary by error kind instead of pattern-matching on
+/// rendered strings. `Display` produces the same text the validator has
+/// always printed to the console.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "code")]
+pub enum ValidationError {
+    MissingRequiredField { field: String },
+    WrongType { field: String, expected: String, found: String },
+    WrongSize { field: String, expected: String, actual: usize },
+    UnknownMessageType { found: String },
+    InvalidBase64 { field: String, source: String },
+    MalformedCbor { source: String },
+    NotTaggedItem,
+    TagMismatch { expected: u32, found: u64 },
+    NotAMap,
+}
+
+impl ValidationError {
+    /// Machine-readable identifier, stable across message wording changes.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ValidationError::MissingRequiredField { .. } => "missing_required_field",
+            ValidationError::WrongType { .. } => "wrong_type",
+            ValidationError::WrongSize { .. } => "wrong_size",
+            ValidationError::UnknownMessageType { .. } => "unknown_message_type",
+            ValidationError::InvalidBase64 { .. } => "invalid_base64",
+            ValidationError::MalformedCbor { .. } => "malformed_cbor",
+            ValidationError::NotTaggedItem => "not_tagged_item",
+            ValidationError::TagMismatch { .. } => "tag_mismatch",
+            ValidationError::NotAMap => "not_a_map",
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::MissingRequiredField { field } if field == "type" => {
+                write!(f, "Missing 'type' field")
+            }
+            ValidationError::MissingRequiredField { field } => {
+                write!(f, "Missing required field: {}", field)
+            }
+            ValidationError::WrongType { field, expected, .. } if field == "type" => {
+                write!(f, "Type field must be {}", expected)
+            }
+            ValidationError::WrongType { field, expected, .. } => {
+                write!(f, "Field {} must be {}", field, expected)
+            }
+            ValidationError::WrongSize { field, expected, actual } => {
+                let kind = match expected.chars().next() {
+                    Some('<') => "too small",
+                    Some('>') => "too large",
+                    _ => "wrong size",
+                };
+                write!(f, "Field {} {}: {} {}", field, kind, actual, expected)
+            }
+            ValidationError::UnknownMessageType { found } => {
+                write!(f, "Unknown message type: {}", found)
+            }
+            ValidationError::InvalidBase64 { field, source } => {
+                write!(f, "Field {} must be valid base64 (error: {})", field, source)
+            }
+            ValidationError::MalformedCbor { source } => {
+                write!(f, "Failed to decode CBOR: {}", source)
+            }
+            ValidationError::NotTaggedItem => write!(f, "CBOR document is not a tagged item"),
+            ValidationError::TagMismatch { expected, found } => {
+                write!(f, "CBOR tag mismatch: expected 0x{:X}, found 0x{:X}", expected, found)
+            }
+            ValidationError::NotAMap => write!(f, "Tagged CBOR item is not a map"),
+        }
+    }
+}
+
+/// Short name for a `serde_json::Value`'s runtime type, used to fill in
+/// the `found` slot of `ValidationError::WrongType`.
+fn json_type_name(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "bool",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+/// Short name for a `CborValue`'s runtime type, used to fill in the
+/// `found` slot of `ValidationError::WrongType` on the CBOR path.
+fn cbor_type_name(value: &CborValue) -> &'static str {
+    match value {
+        CborValue::Integer(_) => "integer",
+        CborValue::Bytes(_) => "bytes",
+        CborValue::Text(_) => "string",
+        CborValue::Array(_) => "array",
+        CborValue::Map(_) => "map",
+        CborValue::Bool(_) => "bool",
+        CborValue::Null => "null",
+        CborValue::Tag(_, _) => "tag",
+        CborValue::Float(_) => "float",
+        _ => "unknown",
+    }
+}
+
+/// Shared by both the JSON path (`validate_base64_field`, after base64
+/// decoding) and the CBOR path (`validate_cbor_field`, on the raw byte
+/// string payload) so the two decoders enforce identical size rules.
+fn check_size_constraints(field_name: &str, byte_len: usize, field_def: &FieldDefinition) -> Result<(), ValidationError> {
+    if let Some(expected_size) = field_def.size_bytes {
+        if byte_len != expected_size {
+            return Err(ValidationError::WrongSize {
+                field: field_name.to_string(),
+                expected: format!("!= {}", expected_size),
+                actual: byte_len,
+            });
+        }
+    }
+
+    if let Some(min_size) = field_def.min_size {
+        if byte_len < min_size {
+            return Err(ValidationError::WrongSize {
+                field: field_name.to_string(),
+                expected: format!("< {}", min_size),
+                actual: byte_len,
+            });
+        }
+    }
+
+    if let Some(max_size) = field_def.max_size {
+        if byte_len > max_size {
+            return Err(ValidationError::WrongSize {
+                field: field_name.to_string(),
+                expected: format!("> {}", max_size),
+                actual: byte_len,
+            });
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SchemaValidationResult {
     pub valid: bool,
-    pub errors: Vec<String>,
+    pub errors: Vec<ValidationError>,
     pub warnings: Vec<String>,
     pub message_type: Option<String>,
     pub schema_version: String,
 }
 
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FieldDefinition {
     pub field_type: String,
@@ -41,6 +187,12 @@ pub struct SchemaValidator {
     schema_version: String,
 }
 
+impl Default for SchemaValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SchemaValidator {
     pub fn new() -> Self {
         let mut validator = Self {
@@ -263,7 +415,7 @@ impl SchemaValidator {
         let type_value = match message_data.get("type") {
             Some(value) => value,
             None => {
-                result.errors.push("Missing 'type' field".to_string());
+                result.errors.push(ValidationError::MissingRequiredField { field: "type".to_string() });
                 return result;
             }
         };
@@ -271,7 +423,11 @@ impl SchemaValidator {
         let message_type_str = match type_value.as_str() {
             Some(s) => s,
             None => {
-                result.errors.push("Type field must be string".to_string());
+                result.errors.push(ValidationError::WrongType {
+                    field: "type".to_string(),
+                    expected: "string".to_string(),
+                    found: json_type_name(type_value).to_string(),
+                });
                 return result;
             }
         };
@@ -282,7 +438,7 @@ impl SchemaValidator {
         let schema = match self.schemas.get(message_type_str) {
             Some(s) => s,
             None => {
-                result.errors.push(format!("Unknown message type: {}", message_type_str));
+                result.errors.push(ValidationError::UnknownMessageType { found: message_type_str.to_string() });
                 return result;
             }
         };
@@ -290,7 +446,7 @@ impl SchemaValidator {
         // Check required fields
         for field in &schema.required_fields {
             if !message_data.contains_key(field) {
-                result.errors.push(format!("Missing required field: {}", field));
+                result.errors.push(ValidationError::MissingRequiredField { field: field.clone() });
             }
         }
 
@@ -316,12 +472,20 @@ impl SchemaValidator {
         match field_def.field_type.as_str() {
             "string" => {
                 if !value.is_string() {
-                    result.errors.push(format!("Field {} must be string", field_name));
+                    result.errors.push(ValidationError::WrongType {
+                        field: field_name.to_string(),
+                        expected: "string".to_string(),
+                        found: json_type_name(value).to_string(),
+                    });
                 }
             }
             "integer" => {
                 if !value.is_number() {
-                    result.errors.push(format!("Field {} must be integer", field_name));
+                    result.errors.push(ValidationError::WrongType {
+                        field: field_name.to_string(),
+                        expected: "integer".to_string(),
+                        found: json_type_name(value).to_string(),
+                    });
                 }
             }
             "base64" => {
@@ -335,37 +499,155 @@ impl SchemaValidator {
         }
     }
 
-    fn validate_base64_field(&self, field_name: &str, value: &serde_json::Value, field_def: &FieldDefinition) -> Result<(), String> {
+    fn validate_base64_field(&self, field_name: &str, value: &serde_json::Value, field_def: &FieldDefinition) -> Result<(), ValidationError> {
         let str_value = match value.as_str() {
             Some(s) => s,
-            None => return Err(format!("Field {} must be string", field_name)),
+            None => {
+                return Err(ValidationError::WrongType {
+                    field: field_name.to_string(),
+                    expected: "string".to_string(),
+                    found: json_type_name(value).to_string(),
+                })
+            }
         };
 
         // Try standard base64 first
         let bytes = general_purpose::STANDARD.decode(str_value)
             .or_else(|_| general_purpose::URL_SAFE.decode(str_value))
-            .map_err(|e| format!("Field {} must be valid base64 (error: {})", field_name, e))?;
+            .map_err(|e| ValidationError::InvalidBase64 { field: field_name.to_string(), source: e.to_string() })?;
 
-        // Check size constraints
-        if let Some(expected_size) = field_def.size_bytes {
-            if bytes.len() != expected_size {
-                return Err(format!("Field {} wrong size: {} != {}", field_name, bytes.len(), expected_size));
+        check_size_constraints(field_name, bytes.len(), field_def)
+    }
+
+    /// Decodes a raw CBOR document and validates it the same way
+    /// `validate_message` validates a pre-parsed JSON object: the outer
+    /// value must be a CBOR tagged item whose tag matches the resolved
+    /// schema's `tag`, and byte-string fields are size-checked directly
+    /// against their decoded length instead of being base64-decoded first.
+    pub fn validate_cbor_bytes(&self, data: &[u8]) -> SchemaValidationResult {
+        let mut result = SchemaValidationResult {
+            valid: false,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            message_type: None,
+            schema_version: self.schema_version.clone(),
+        };
+
+        let value: CborValue = match ciborium::de::from_reader(data) {
+            Ok(v) => v,
+            Err(e) => {
+                result.errors.push(ValidationError::MalformedCbor { source: e.to_string() });
+                return result;
+            }
+        };
+
+        let (tag, inner) = match value {
+            CborValue::Tag(tag, inner) => (tag, *inner),
+            _ => {
+                result.errors.push(ValidationError::NotTaggedItem);
+                return result;
+            }
+        };
+
+        let map = match inner.as_map() {
+            Some(m) => m,
+            None => {
+                result.errors.push(ValidationError::NotAMap);
+                return result;
+            }
+        };
+
+        let fields: HashMap<String, &CborValue> = map
+            .iter()
+            .filter_map(|(k, v)| k.as_text().map(|key| (key.to_string(), v)))
+            .collect();
+
+        let message_type_str = match fields.get("type").and_then(|v| v.as_text()) {
+            Some(s) => s.to_string(),
+            None => {
+                result.errors.push(ValidationError::MissingRequiredField { field: "type".to_string() });
+                return result;
+            }
+        };
+
+        result.message_type = Some(message_type_str.clone());
+
+        let schema = match self.schemas.get(&message_type_str) {
+            Some(s) => s,
+            None => {
+                result.errors.push(ValidationError::UnknownMessageType { found: message_type_str });
+                return result;
+            }
+        };
+
+        if tag != schema.tag as u64 {
+            result.errors.push(ValidationError::TagMismatch { expected: schema.tag, found: tag });
+        }
+
+        for field in &schema.required_fields {
+            if !fields.contains_key(field) {
+                result.errors.push(ValidationError::MissingRequiredField { field: field.clone() });
             }
         }
 
-        if let Some(min_size) = field_def.min_size {
-            if bytes.len() < min_size {
-                return Err(format!("Field {} too small: {} < {}", field_name, bytes.len(), min_size));
+        for field_name in fields.keys() {
+            if !schema.required_fields.contains(field_name) && !schema.optional_fields.contains(field_name) {
+                result.warnings.push(format!("Unknown field: {}", field_name));
             }
         }
 
-        if let Some(max_size) = field_def.max_size {
-            if bytes.len() > max_size {
-                return Err(format!("Field {} too large: {} > {}", field_name, bytes.len(), max_size));
+        for (field_name, field_value) in &fields {
+            if let Some(field_def) = schema.field_definitions.get(field_name) {
+                self.validate_cbor_field(field_name, field_value, field_def, &mut result);
             }
         }
 
-        Ok(())
+        result.valid = result.errors.is_empty();
+        result
+    }
+
+    /// CBOR counterpart of `validate_field`: maps CBOR major types onto the
+    /// schema's `field_type` (text string -> "string", integer -> "integer",
+    /// byte string -> "base64"'s underlying byte payload) instead of
+    /// inspecting a `serde_json::Value`.
+    fn validate_cbor_field(&self, field_name: &str, value: &CborValue, field_def: &FieldDefinition, result: &mut SchemaValidationResult) {
+        match field_def.field_type.as_str() {
+            "string" => {
+                if value.as_text().is_none() {
+                    result.errors.push(ValidationError::WrongType {
+                        field: field_name.to_string(),
+                        expected: "string".to_string(),
+                        found: cbor_type_name(value).to_string(),
+                    });
+                }
+            }
+            "integer" => {
+                if value.as_integer().is_none() {
+                    result.errors.push(ValidationError::WrongType {
+                        field: field_name.to_string(),
+                        expected: "integer".to_string(),
+                        found: cbor_type_name(value).to_string(),
+                    });
+                }
+            }
+            "base64" => match value.as_bytes() {
+                Some(bytes) => {
+                    if let Err(e) = check_size_constraints(field_name, bytes.len(), field_def) {
+                        result.errors.push(e);
+                    }
+                }
+                None => {
+                    result.errors.push(ValidationError::WrongType {
+                        field: field_name.to_string(),
+                        expected: "CBOR byte string".to_string(),
+                        found: cbor_type_name(value).to_string(),
+                    });
+                }
+            },
+            _ => {
+                result.warnings.push(format!("Unknown field type: {} for field {}", field_def.field_type, field_name));
+            }
+        }
     }
 
     pub fn validate_test_vectors(&self) -> Result<Vec<SchemaValidationResult>, Box<dyn Error>> {
@@ -457,6 +739,9 @@ impl SchemaValidator {
     }
 }
 
+// Also built as a lib crate (see `validate_cbor_schema_rust/Cargo.toml`) for
+// the fuzz harness, which never calls this entry point.
+#[allow(dead_code)]
 fn main() -> Result<(), Box<dyn Error>> {
     println!("FoxWhisper CBOR Schema Validator - Rust Implementation");
     println!("{}", "=".repeat(50));
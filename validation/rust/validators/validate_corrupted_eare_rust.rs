@@ -1,9 +1,12 @@
+use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
 use serde::{Deserialize, Serialize};
+use serde_cbor::Value as CborValue;
 use std::collections::HashMap;
 use std::error::Error;
 
 mod util;
-use util::{load_json, write_json};
+use util::{load_json, write_json, write_text};
 
 #[derive(Debug, Deserialize)]
 struct GroupContext {
@@ -20,6 +23,14 @@ struct Node {
     issued_by: String,
     previous_epoch_hash: String,
     membership_digest: String,
+    /// Ed25519 signature (base64) by the `issued_by` key over
+    /// `canonical_node_bytes(self)`.
+    issuer_signature: String,
+    /// Ed25519 signature (base64) by `ephemeral_public_key` over
+    /// `membership_digest`, proving the node holds that ephemeral private key.
+    pop_signature: String,
+    /// Ed25519 public key (base64) of the node's ephemeral proof-of-possession key.
+    ephemeral_public_key: String,
     #[serde(default)]
     payload: Option<serde_json::Value>,
 }
@@ -53,12 +64,110 @@ struct Scenario {
     #[serde(default)]
     tags: Vec<String>,
     group_context: GroupContext,
+    /// JWKS-style key set: maps each `Node.issued_by` identifier to the
+    /// base64 Ed25519 verification key that should have signed it.
+    issuer_keys: HashMap<String, String>,
     nodes: Vec<Node>,
     #[serde(default)]
     corruptions: Vec<Corruption>,
     expectations: Expectations,
 }
 
+/// Canonically encodes the fields an issuer signs over, in a fixed field
+/// order so both the signer and the verifier agree on the exact bytes
+/// without needing a canonical-map-ordering rule.
+fn canonical_node_bytes(node: &Node) -> Result<Vec<u8>, serde_cbor::Error> {
+    let fields = CborValue::Array(vec![
+        CborValue::Text(node.node_id.clone()),
+        CborValue::Integer(node.epoch_id as i128),
+        CborValue::Text(node.eare_hash.clone()),
+        CborValue::Text(node.previous_epoch_hash.clone()),
+        CborValue::Text(node.membership_digest.clone()),
+    ]);
+    serde_cbor::to_vec(&fields)
+}
+
+fn decode_ed25519_key(b64: &str) -> Result<VerifyingKey, Box<dyn Error>> {
+    let bytes: [u8; 32] = general_purpose::STANDARD
+        .decode(b64)?
+        .try_into()
+        .map_err(|_| "Ed25519 public key must be 32 bytes")?;
+    Ok(VerifyingKey::from_bytes(&bytes)?)
+}
+
+fn decode_ed25519_signature(b64: &str) -> Result<Signature, Box<dyn Error>> {
+    let bytes: [u8; 64] = general_purpose::STANDARD
+        .decode(b64)?
+        .try_into()
+        .map_err(|_| "Ed25519 signature must be 64 bytes")?;
+    Ok(Signature::from_bytes(&bytes))
+}
+
+/// Real verification of the issuer's signature over the node's
+/// authenticated fields, selecting the verification key by `issued_by`
+/// exactly as a JWKS-backed JWT validator selects a key by `kid`.
+fn try_verify_issuer_signature(node: &Node, issuer_keys: &HashMap<String, String>) -> Result<bool, Box<dyn Error>> {
+    let key_b64 = issuer_keys
+        .get(&node.issued_by)
+        .ok_or("no issuer key registered for this node's issuer")?;
+    let verifying_key = decode_ed25519_key(key_b64)?;
+    let signature = decode_ed25519_signature(&node.issuer_signature)?;
+    let message = canonical_node_bytes(node)?;
+    Ok(verifying_key.verify(&message, &signature).is_ok())
+}
+
+fn verify_issuer_signature(node: &Node, issuer_keys: &HashMap<String, String>) -> bool {
+    try_verify_issuer_signature(node, issuer_keys).unwrap_or(false)
+}
+
+/// Real verification of the proof-of-possession signature: the node's
+/// ephemeral key signs `membership_digest`, binding that ephemeral key to
+/// the membership state it is being admitted under.
+fn try_verify_pop(node: &Node) -> Result<bool, Box<dyn Error>> {
+    let verifying_key = decode_ed25519_key(&node.ephemeral_public_key)?;
+    let signature = decode_ed25519_signature(&node.pop_signature)?;
+    Ok(verifying_key.verify(node.membership_digest.as_bytes(), &signature).is_ok())
+}
+
+fn verify_pop(node: &Node) -> bool {
+    try_verify_pop(node).unwrap_or(false)
+}
+
+/// Applies a corruption's `fields` patch directly onto the authenticated
+/// fields it names. A corruption of any type whose `fields` overwrite a
+/// signed field fails `verify_issuer_signature` on its own: the signature
+/// was computed over the original bytes. `payload` is not among the signed
+/// bytes, so a `payload_patch` alone does not break the signature.
+fn apply_field_overrides(node: &mut Node, fields: &serde_json::Value) {
+    let Some(obj) = fields.as_object() else { return };
+    for (key, value) in obj {
+        match key.as_str() {
+            "node_id" => if let Some(s) = value.as_str() { node.node_id = s.to_string(); },
+            "epoch_id" => if let Some(n) = value.as_i64() { node.epoch_id = n as i32; },
+            "eare_hash" => if let Some(s) = value.as_str() { node.eare_hash = s.to_string(); },
+            "issued_by" => if let Some(s) = value.as_str() { node.issued_by = s.to_string(); },
+            "previous_epoch_hash" => if let Some(s) = value.as_str() { node.previous_epoch_hash = s.to_string(); },
+            "membership_digest" => if let Some(s) = value.as_str() { node.membership_digest = s.to_string(); },
+            "issuer_signature" => if let Some(s) = value.as_str() { node.issuer_signature = s.to_string(); },
+            "pop_signature" => if let Some(s) = value.as_str() { node.pop_signature = s.to_string(); },
+            "ephemeral_public_key" => if let Some(s) = value.as_str() { node.ephemeral_public_key = s.to_string(); },
+            _ => {}
+        }
+    }
+}
+
+/// Merges (or replaces) `node.payload` with a corruption's `payload_patch`.
+fn apply_payload_patch(node: &mut Node, patch: &serde_json::Value) {
+    match (&mut node.payload, patch) {
+        (Some(serde_json::Value::Object(existing)), serde_json::Value::Object(patch_obj)) => {
+            for (k, v) in patch_obj {
+                existing.insert(k.clone(), v.clone());
+            }
+        }
+        _ => node.payload = Some(patch.clone()),
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct ScenarioSummary {
     scenario_id: String,
@@ -85,6 +194,13 @@ struct SimulationResult {
     errors: Vec<String>,
     metrics: serde_json::Value,
     notes: Vec<String>,
+    /// Nodes in epoch order, with any corruption field/payload overrides
+    /// already applied — what `render_epoch_chain_dot` graphs.
+    nodes: Vec<Node>,
+    /// Node ids rejected for a hash-chain break, failed signature/PoP
+    /// verification, or a `HASH_CHAIN_BREAK`/`TRUNCATED_EARE` corruption
+    /// targeting them — what `render_epoch_chain_dot` highlights.
+    rejected_node_ids: std::collections::HashSet<String>,
 }
 
 fn push_err(errors: &mut Vec<String>, code: &str) {
@@ -106,39 +222,73 @@ fn simulate(s: &Scenario) -> SimulationResult {
     let mut nodes = s.nodes.clone();
     nodes.sort_by_key(|n| n.epoch_id);
 
+    // Apply field/payload corruptions before any verification runs, so a
+    // corruption that overwrites a signed field is reflected in the bytes
+    // that get verified below.
+    for node in nodes.iter_mut() {
+        let targets = [node.node_id.clone(), "*".to_string()];
+        for t in &targets {
+            if let Some(corrs) = corr_by_target.get(t) {
+                for c in corrs {
+                    if let Some(fields) = &c.fields {
+                        apply_field_overrides(node, fields);
+                    }
+                    if let Some(patch) = &c.payload_patch {
+                        apply_payload_patch(node, patch);
+                    }
+                }
+            }
+        }
+    }
+
     let mut last_hash: Option<String> = None;
     let mut hash_breaks = 0;
     let mut accepted = 0;
     let mut rejected = 0;
+    let mut rejected_node_ids: std::collections::HashSet<String> = std::collections::HashSet::new();
 
     for node in nodes.iter() {
+        let mut node_rejected = false;
         if let Some(prev) = &last_hash {
             if node.previous_epoch_hash != *prev {
                 push_err(&mut errors, "HASH_CHAIN_BREAK");
                 hash_breaks += 1;
-                rejected += 1;
-            } else {
-                accepted += 1;
+                node_rejected = true;
+                rejected_node_ids.insert(node.node_id.clone());
             }
-        } else {
-            accepted += 1;
         }
         last_hash = Some(node.eare_hash.clone());
 
+        // Genuine Ed25519 verification, not a tag lookup: a corruption that
+        // tampered a signed field above will make these fail on their own.
+        if !verify_issuer_signature(node, &s.issuer_keys) {
+            push_err(&mut errors, "INVALID_SIGNATURE");
+            node_rejected = true;
+            rejected_node_ids.insert(node.node_id.clone());
+        }
+        if !verify_pop(node) {
+            push_err(&mut errors, "INVALID_POP");
+            node_rejected = true;
+            rejected_node_ids.insert(node.node_id.clone());
+        }
+
         let targets = vec![node.node_id.clone(), "*".to_string()];
         for t in targets {
             if let Some(corrs) = corr_by_target.get(&t) {
                 for c in corrs {
                     match c.r#type.to_uppercase().as_str() {
-                        "INVALID_SIGNATURE" => push_err(&mut errors, "INVALID_SIGNATURE"),
-                        "INVALID_POP" => push_err(&mut errors, "INVALID_POP"),
+                        // Handled above via real signature/PoP verification.
+                        "INVALID_SIGNATURE" | "INVALID_POP" => {}
                         "HASH_CHAIN_BREAK" => {
                             push_err(&mut errors, "HASH_CHAIN_BREAK");
                             hash_breaks += 1;
+                            node_rejected = true;
+                            rejected_node_ids.insert(node.node_id.clone());
                         }
                         "TRUNCATED_EARE" => {
                             push_err(&mut errors, "TRUNCATED_EARE");
-                            rejected += 1;
+                            node_rejected = true;
+                            rejected_node_ids.insert(node.node_id.clone());
                         }
                         "EXTRA_FIELDS" => push_err(&mut errors, "EXTRA_FIELDS"),
                         "PAYLOAD_TAMPERED" | "TAMPER_PAYLOAD" => {
@@ -150,6 +300,12 @@ fn simulate(s: &Scenario) -> SimulationResult {
                 }
             }
         }
+
+        if node_rejected {
+            rejected += 1;
+        } else {
+            accepted += 1;
+        }
     }
 
     let detection = !errors.is_empty();
@@ -169,7 +325,60 @@ fn simulate(s: &Scenario) -> SimulationResult {
         errors,
         metrics,
         notes,
+        nodes,
+        rejected_node_ids,
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Renders the epoch hash-chain as a Graphviz `digraph`: one node per
+/// `Node`, an edge from each node to the node before it in epoch order,
+/// drawn red dashed where the node's `previous_epoch_hash` does not match
+/// that predecessor's `eare_hash`, and
+/// rejected nodes filled a distinct color — so a maintainer can see where a
+/// hash chain diverges instead of reading the `hash_chain_breaks` counter.
+fn render_epoch_chain_dot(nodes: &[Node], rejected_node_ids: &std::collections::HashSet<String>) -> String {
+    let mut dot = String::from("digraph epoch_chain {\n  rankdir=LR;\n");
+
+    for node in nodes {
+        let label = format!("{}\\nepoch {}\\nissued_by {}", node.node_id, node.epoch_id, node.issued_by);
+        if rejected_node_ids.contains(&node.node_id) {
+            dot.push_str(&format!(
+                "  \"{}\" [label=\"{}\", style=filled, fillcolor=\"#f4a3a3\"];\n",
+                dot_escape(&node.node_id),
+                dot_escape(&label)
+            ));
+        } else {
+            dot.push_str(&format!(
+                "  \"{}\" [label=\"{}\"];\n",
+                dot_escape(&node.node_id),
+                dot_escape(&label)
+            ));
+        }
     }
+
+    for pair in nodes.windows(2) {
+        let (prev, current) = (&pair[0], &pair[1]);
+        if current.previous_epoch_hash != prev.eare_hash {
+            dot.push_str(&format!(
+                "  \"{}\" -> \"{}\" [color=red, style=dashed];\n",
+                dot_escape(&current.node_id),
+                dot_escape(&prev.node_id)
+            ));
+        } else {
+            dot.push_str(&format!(
+                "  \"{}\" -> \"{}\";\n",
+                dot_escape(&current.node_id),
+                dot_escape(&prev.node_id)
+            ));
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
 }
 
 fn evaluate(exp: &Expectations, res: &SimulationResult) -> (String, Vec<String>) {
@@ -240,6 +449,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         } else {
             summary.failed += 1;
         }
+
+        let dot = render_epoch_chain_dot(&res.nodes, &res.rejected_node_ids);
+        write_text(&format!("{}_epoch_chain.dot", scenario.scenario_id), &dot)?;
+
         summary.scenarios.push(ScenarioSummary {
             scenario_id: scenario.scenario_id.clone(),
             status,
@@ -3,9 +3,9 @@ use std::collections::{HashMap, HashSet};
 use std::error::Error;
 
 mod util;
-use util::{load_json, write_json};
+use util::{load_json, write_json, write_text};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct Device {
     device_id: String,
     dr_version: i32,
@@ -14,7 +14,7 @@ struct Device {
     state_hash: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 struct Event {
     t: i32,
     event: String,
@@ -40,7 +40,7 @@ struct Event {
     target_dr_version: Option<i32>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct Expectations {
     detected: bool,
     max_detection_ms: i32,
@@ -53,9 +53,22 @@ struct Expectations {
     allow_out_of_order_rate: f64,
     expected_error_categories: Vec<String>,
     max_rollback_events: i32,
+    /// Diagnostic codes (e.g. `"clock_skew_exceeded"`) that should be
+    /// reported as `Warning` instead of `Error` for this scenario, so a
+    /// tolerated divergence class doesn't flip the scenario to `fail`.
+    #[serde(default)]
+    warn_only: Vec<String>,
+    /// Backoff-queue tuning for automatic resync attempts; `0` (the
+    /// default) falls back to the harness's built-in defaults.
+    #[serde(default)]
+    resync_base_delay_ms: i32,
+    #[serde(default)]
+    resync_max_delay_ms: i32,
+    #[serde(default)]
+    max_resync_attempts: i32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct Scenario {
     scenario_id: String,
     #[serde(default)]
@@ -70,6 +83,8 @@ struct ScenarioSummary {
     scenario_id: String,
     status: String,
     failures: Vec<String>,
+    warnings: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
     errors: Vec<String>,
     metrics: serde_json::Value,
     notes: Vec<String>,
@@ -84,9 +99,13 @@ struct Summary {
     scenarios: Vec<ScenarioSummary>,
 }
 
+/// A device's causal state is a version vector: each originating device's
+/// own counter as last witnessed, rather than one scalar shared by
+/// everybody. `vector[&device_id]` is the device's own progress; other
+/// entries are what it has learned about others via `recv`/resync merges.
 #[derive(Clone)]
 struct DeviceState {
-    dr_version: i32,
+    vector: HashMap<String, i64>,
     clock_ms: i32,
     state_hash: Option<String>,
 }
@@ -94,7 +113,7 @@ struct DeviceState {
 struct Message {
     sender: String,
     targets: Vec<String>,
-    dr_version: i32,
+    vector: HashMap<String, i64>,
     state_hash: Option<String>,
     send_time: i32,
     delivered: HashSet<String>,
@@ -102,6 +121,233 @@ struct Message {
     replay_count: i32,
 }
 
+/// Distinguishes how `drain_ready_resyncs` applies a `PendingResync`'s
+/// `target_vector` once it's ready: an authored resync overwrites (so a
+/// stale authored target can still express a deliberate rollback), while
+/// an auto-heal resync merges component-wise against whatever the device
+/// has learned by drain time, since its `target_vector` is only a snapshot
+/// of the frontier taken back when the entry was enqueued.
+enum ResyncKind {
+    Authored,
+    AutoHeal,
+}
+
+/// One device's slot in the backoff resync queue: the vector it's being
+/// healed towards (applied by `drain_ready_resyncs` according to `kind`),
+/// how many attempts have been made, and when the next attempt is
+/// scheduled.
+struct PendingResync {
+    target_vector: HashMap<String, i64>,
+    kind: ResyncKind,
+    attempts: i32,
+    next_attempt_t: i32,
+}
+
+fn vector_get(v: &HashMap<String, i64>, key: &str) -> i64 {
+    v.get(key).copied().unwrap_or(0)
+}
+
+fn vector_keys<'a>(a: &'a HashMap<String, i64>, b: &'a HashMap<String, i64>) -> HashSet<&'a str> {
+    a.keys()
+        .map(|k| k.as_str())
+        .chain(b.keys().map(|k| k.as_str()))
+        .collect()
+}
+
+fn vector_eq(a: &HashMap<String, i64>, b: &HashMap<String, i64>) -> bool {
+    vector_keys(a, b).iter().all(|k| vector_get(a, k) == vector_get(b, k))
+}
+
+/// `a` causally dominates `b` if it has seen everything `b` has seen (every
+/// component at least as large) and strictly more in at least one
+/// component. Neither side dominating the other means the two states are
+/// concurrent — a genuine fork, not a simple rollback.
+fn dominates(a: &HashMap<String, i64>, b: &HashMap<String, i64>) -> bool {
+    let mut strictly_greater = false;
+    for k in vector_keys(a, b) {
+        let (av, bv) = (vector_get(a, k), vector_get(b, k));
+        if av < bv {
+            return false;
+        }
+        if av > bv {
+            strictly_greater = true;
+        }
+    }
+    strictly_greater
+}
+
+fn concurrent(a: &HashMap<String, i64>, b: &HashMap<String, i64>) -> bool {
+    !vector_eq(a, b) && !dominates(a, b) && !dominates(b, a)
+}
+
+/// Size of the gap by which `a` dominates `b` (`0` if it doesn't): the sum
+/// of each component's positive difference. Replaces the old scalar
+/// `max - min` as the basis for `max_dr_version_delta`.
+fn vector_gap(a: &HashMap<String, i64>, b: &HashMap<String, i64>) -> i64 {
+    vector_keys(a, b)
+        .iter()
+        .map(|k| (vector_get(a, k) - vector_get(b, k)).max(0))
+        .sum()
+}
+
+fn merge_into(receiver: &mut HashMap<String, i64>, incoming: &HashMap<String, i64>) {
+    for (k, v) in incoming {
+        let entry = receiver.entry(k.clone()).or_insert(0);
+        if *v > *entry {
+            *entry = *v;
+        }
+    }
+}
+
+/// Synthetic "fully caught up" reference point: the component-wise max
+/// across every device's vector. No single device need actually be in this
+/// state; it's the causal frontier the fleet has collectively witnessed.
+fn frontier(devices: &HashMap<String, DeviceState>) -> HashMap<String, i64> {
+    let mut front: HashMap<String, i64> = HashMap::new();
+    for dev in devices.values() {
+        merge_into(&mut front, &dev.vector);
+    }
+    front
+}
+
+/// Replaces the old scalar `current_dr_stats` under the vector model:
+/// `max_gap` is the largest dominated gap between the frontier and any
+/// single device (what `max_dr_version_delta` used to mean), `concurrent_pairs`
+/// counts device pairs where neither dominates the other (a genuine fork),
+/// and `diverged_count` counts devices not already at the frontier.
+fn fleet_stats(devices: &HashMap<String, DeviceState>) -> (i64, i32, i32) {
+    let front = frontier(devices);
+    let max_gap = devices
+        .values()
+        .map(|d| vector_gap(&front, &d.vector))
+        .max()
+        .unwrap_or(0);
+    let diverged_count = devices
+        .values()
+        .filter(|d| !vector_eq(&d.vector, &front))
+        .count() as i32;
+
+    let states: Vec<&HashMap<String, i64>> = devices.values().map(|d| &d.vector).collect();
+    let mut concurrent_pairs = 0;
+    for i in 0..states.len() {
+        for j in (i + 1)..states.len() {
+            if concurrent(states[i], states[j]) {
+                concurrent_pairs += 1;
+            }
+        }
+    }
+
+    (max_gap, concurrent_pairs, diverged_count)
+}
+
+/// A message is an immediate causal successor of the receiver's current
+/// vector if it advances the sender's own component by exactly one step and
+/// carries no causal knowledge the receiver couldn't already account for (no
+/// other component exceeds what the receiver has already seen). Anything
+/// else is a true causal violation rather than a wall-clock reordering.
+fn is_immediate_successor(
+    incoming: &HashMap<String, i64>,
+    receiver: &HashMap<String, i64>,
+    sender: &str,
+) -> bool {
+    if vector_get(incoming, sender) != vector_get(receiver, sender) + 1 {
+        return false;
+    }
+    incoming
+        .iter()
+        .filter(|(k, _)| k.as_str() != sender)
+        .all(|(k, v)| *v <= vector_get(receiver, k))
+}
+
+/// Runs every pending resync whose `next_attempt_t` has arrived by `now`.
+/// An attempt applies its `target_vector` to the device according to its
+/// `ResyncKind` (see there) and checks whether the fleet has converged; if
+/// not, it reschedules itself at `now + base_delay_ms * 2^attempts` (capped
+/// at `max_delay_ms`), and gives up with `RESYNC_BUDGET_EXCEEDED` once
+/// `attempts` reaches `max_resync_attempts`.
+#[allow(clippy::too_many_arguments)]
+fn drain_ready_resyncs(
+    now: i32,
+    pending: &mut HashMap<String, PendingResync>,
+    devices: &mut HashMap<String, DeviceState>,
+    base_delay_ms: i32,
+    max_delay_ms: i32,
+    max_resync_attempts: i32,
+    scheduled_resyncs: &mut i32,
+    total_backoff_ms: &mut i64,
+    recovery_attempts: &mut i32,
+    successful_recoveries: &mut i32,
+    failed_recoveries: &mut i32,
+    max_rollback: &mut i32,
+    errors: &mut Vec<String>,
+    recovery_time: &mut Option<i32>,
+) {
+    let ready: Vec<String> = pending
+        .iter()
+        .filter(|(_, p)| p.next_attempt_t <= now)
+        .map(|(device, _)| device.clone())
+        .collect();
+
+    for device in ready {
+        let mut entry = match pending.remove(&device) {
+            Some(e) => e,
+            None => continue,
+        };
+        *recovery_attempts += 1;
+        if let Some(dev) = devices.get_mut(&device) {
+            match entry.kind {
+                ResyncKind::Authored => {
+                    for (key, new_v) in entry.target_vector.iter() {
+                        let old_v = vector_get(&dev.vector, key);
+                        if *new_v < old_v {
+                            let rollback = (old_v - new_v) as i32;
+                            if rollback > *max_rollback {
+                                *max_rollback = rollback;
+                            }
+                        }
+                        dev.vector.insert(key.clone(), *new_v);
+                    }
+                }
+                ResyncKind::AutoHeal => {
+                    // Merge rather than overwrite: the snapshot was taken at
+                    // enqueue time, and an intervening `recv` may have since
+                    // advanced `dev.vector` past it for some component. A
+                    // merge can never regress a component the device
+                    // already knows about, so it can't manufacture a
+                    // spurious rollback the way overwriting a stale
+                    // snapshot would.
+                    merge_into(&mut dev.vector, &entry.target_vector);
+                }
+            }
+        }
+
+        let (after_gap, _, _) = fleet_stats(devices);
+        if after_gap == 0 {
+            *successful_recoveries += 1;
+            if recovery_time.is_none() {
+                *recovery_time = Some(now);
+            }
+        } else {
+            entry.attempts += 1;
+            if entry.attempts >= max_resync_attempts {
+                *failed_recoveries += 1;
+                if !contains(errors, "RESYNC_BUDGET_EXCEEDED") {
+                    errors.push("RESYNC_BUDGET_EXCEEDED".to_string());
+                }
+            } else {
+                let delay = std::cmp::min(
+                    base_delay_ms.saturating_mul(1i32 << entry.attempts),
+                    max_delay_ms,
+                );
+                entry.next_attempt_t = now + delay;
+                *total_backoff_ms += delay as i64;
+                *scheduled_resyncs += 1;
+                pending.insert(device, entry);
+            }
+        }
+    }
+}
+
 struct SimulationResult {
     detection: bool,
     detection_ms: Option<i32>,
@@ -115,25 +361,6 @@ fn contains(list: &[String], item: &str) -> bool {
     list.iter().any(|v| v == item)
 }
 
-fn current_dr_stats(devices: &HashMap<String, DeviceState>) -> (i32, i32, i32) {
-    let mut iter = devices.values();
-    let first = iter.next();
-    if first.is_none() {
-        return (0, 0, 0);
-    }
-    let mut min = first.unwrap().dr_version;
-    let mut max = min;
-    for d in iter {
-        if d.dr_version < min {
-            min = d.dr_version;
-        }
-        if d.dr_version > max {
-            max = d.dr_version;
-        }
-    }
-    (min, max, max - min)
-}
-
 fn clock_range(devices: &HashMap<String, DeviceState>) -> i32 {
     let mut iter = devices.values();
     let first = iter.next();
@@ -158,10 +385,12 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
         .devices
         .iter()
         .map(|d| {
+            let mut vector = HashMap::new();
+            vector.insert(d.device_id.clone(), d.dr_version as i64);
             (
                 d.device_id.clone(),
                 DeviceState {
-                    dr_version: d.dr_version,
+                    vector,
                     clock_ms: d.clock_ms,
                     state_hash: d.state_hash.clone(),
                 },
@@ -178,9 +407,10 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
     let mut delivered: i32 = 0;
     let mut expected: i32 = 0;
     let mut out_of_order: i32 = 0;
-    let mut dr_integral = 0;
+    let mut dr_integral: i64 = 0;
     let mut dr_samples = 0;
-    let mut max_dr_delta = 0;
+    let mut max_causal_gap: i64 = 0;
+    let mut max_concurrent_forks = 0;
     let mut max_diverged_count = 0;
     let mut max_clock_skew = 0;
     let mut skew_violations = 0;
@@ -191,6 +421,29 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
     let mut errors: Vec<String> = Vec::new();
     let mut notes: Vec<String> = Vec::new();
 
+    let base_delay_ms = if s.expectations.resync_base_delay_ms > 0 {
+        s.expectations.resync_base_delay_ms
+    } else {
+        50
+    };
+    let max_delay_ms = if s.expectations.resync_max_delay_ms > 0 {
+        s.expectations.resync_max_delay_ms
+    } else {
+        5000
+    };
+    // Clamped to 20: `drain_ready_resyncs` computes `1i32 << attempts` for
+    // the backoff delay with `attempts` up to this value minus one; that
+    // shift yields a negative delay at 31 and panics at 32 or more, so any
+    // scenario-supplied value at or above 32 would break it.
+    let max_resync_attempts = if s.expectations.max_resync_attempts > 0 {
+        s.expectations.max_resync_attempts.min(20)
+    } else {
+        5
+    };
+    let mut pending_resyncs: HashMap<String, PendingResync> = HashMap::new();
+    let mut scheduled_resyncs: i32 = 0;
+    let mut total_backoff_ms: i64 = 0;
+
     let mut events = s.timeline.clone();
     events.sort_by(|a, b| a.t.cmp(&b.t).then_with(|| a.event.cmp(&b.event)));
 
@@ -215,21 +468,55 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
             }
         }
 
+        drain_ready_resyncs(
+            ev.t,
+            &mut pending_resyncs,
+            &mut devices,
+            base_delay_ms,
+            max_delay_ms,
+            max_resync_attempts,
+            &mut scheduled_resyncs,
+            &mut total_backoff_ms,
+            &mut recovery_attempts,
+            &mut successful_recoveries,
+            &mut failed_recoveries,
+            &mut max_rollback,
+            &mut errors,
+            &mut recovery_time,
+        );
+
         match ev.event.as_str() {
             "send" => {
                 let msg_id = ev.msg_id.as_ref().ok_or("send missing msg_id")?;
                 let sender = ev.from.as_ref().ok_or("send missing from")?;
                 let targets = ev.to.clone().unwrap_or_default();
-                let dr_version = ev.dr_version.unwrap_or_else(|| devices[sender].dr_version);
                 let state_hash = ev.state_hash.clone();
+                // The sender advances its own component by one unless the
+                // event explicitly overrides it (e.g. to author a
+                // deliberately stale send for a rollback scenario).
+                let old_self = vector_get(&devices[sender].vector, sender);
+                let new_self = ev.dr_version.map(|v| v as i64).unwrap_or(old_self + 1);
+                if new_self < old_self {
+                    let rollback = (old_self - new_self) as i32;
+                    if rollback > max_rollback {
+                        max_rollback = rollback;
+                    }
+                }
+                if let Some(sender_state) = devices.get_mut(sender) {
+                    sender_state.vector.insert(sender.clone(), new_self);
+                    if let Some(hash) = state_hash.clone() {
+                        sender_state.state_hash = Some(hash);
+                    }
+                }
+                let vector = devices[sender].vector.clone();
                 if !messages.contains_key(msg_id) {
                     messages.insert(
                         msg_id.clone(),
                         Message {
                             sender: sender.clone(),
                             targets: targets.clone(),
-                            dr_version,
-                            state_hash: state_hash.clone(),
+                            vector,
+                            state_hash,
                             send_time: ev.t,
                             delivered: HashSet::new(),
                             dropped: HashSet::new(),
@@ -240,18 +527,6 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
                     msg.replay_count += 1;
                 }
                 expected += targets.len() as i32;
-                if let Some(sender_state) = devices.get_mut(sender) {
-                    if dr_version < sender_state.dr_version {
-                        let rollback = sender_state.dr_version - dr_version;
-                        if rollback > max_rollback {
-                            max_rollback = rollback;
-                        }
-                    }
-                    sender_state.dr_version = dr_version;
-                    if let Some(hash) = state_hash {
-                        sender_state.state_hash = Some(hash);
-                    }
-                }
             }
             "recv" => {
                 let msg_id = ev.msg_id.as_ref().ok_or("recv missing msg_id")?;
@@ -270,19 +545,29 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
                     if envelope.delivered.contains(device) {
                         add_error(&mut errors, &mut detection_time, "DUPLICATE_DELIVERY", None);
                     }
-                    if ev.t < envelope.send_time {
+                    // A true causal violation (the message isn't an
+                    // immediate causal successor of what this device has
+                    // already seen) replaces the old wall-clock comparison.
+                    if !is_immediate_successor(&envelope.vector, &dev.vector, &envelope.sender) {
                         out_of_order += 1;
                     }
+                    merge_into(&mut dev.vector, &envelope.vector);
                     envelope.delivered.insert(device.clone());
                     delivered += 1;
+                    // `apply_dr_version`, when present, forces the device's
+                    // own component after the merge — a local state
+                    // overwrite distinct from the causal merge above, and
+                    // still able to express a rollback.
                     if let Some(apply_ver) = ev.apply_dr_version {
-                        if apply_ver < dev.dr_version {
-                            let rollback = dev.dr_version - apply_ver;
+                        let old_v = vector_get(&dev.vector, device);
+                        let new_v = apply_ver as i64;
+                        if new_v < old_v {
+                            let rollback = (old_v - new_v) as i32;
                             if rollback > max_rollback {
                                 max_rollback = rollback;
                             }
                         }
-                        dev.dr_version = apply_ver;
+                        dev.vector.insert(device.clone(), new_v);
                     }
                     if let Some(hash) = ev.state_hash.clone() {
                         dev.state_hash = Some(hash);
@@ -314,14 +599,20 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
                         format!("[{}] replay unknown device {}", s.scenario_id, sender).into(),
                     );
                 }
-                let dr_version = ev.dr_version.unwrap_or_else(|| devices[sender].dr_version);
+                // A replay doesn't mutate the sender's own state — it
+                // resends a vector snapshot, either the sender's current one
+                // or an explicitly authored (typically stale) override.
+                let mut vector = devices[sender].vector.clone();
+                if let Some(v) = ev.dr_version {
+                    vector.insert(sender.clone(), v as i64);
+                }
                 if !messages.contains_key(msg_id) {
                     messages.insert(
                         msg_id.clone(),
                         Message {
                             sender: sender.clone(),
                             targets: targets.clone(),
-                            dr_version,
+                            vector,
                             state_hash: None,
                             send_time: ev.t,
                             delivered: HashSet::new(),
@@ -346,8 +637,10 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
                 let dev = devices
                     .get_mut(device)
                     .ok_or_else(|| format!("unknown device {device}"))?;
-                if dr_version < dev.dr_version {
-                    let rollback = dev.dr_version - dr_version;
+                let old_v = vector_get(&dev.vector, device);
+                let new_v = dr_version as i64;
+                if new_v < old_v {
+                    let rollback = (old_v - new_v) as i32;
                     if rollback > max_rollback {
                         max_rollback = rollback;
                     }
@@ -358,7 +651,7 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
                         Some(ev.t),
                     );
                 }
-                dev.dr_version = dr_version;
+                dev.vector.insert(device.clone(), new_v);
                 if let Some(hash) = ev.state_hash.clone() {
                     dev.state_hash = Some(hash);
                 }
@@ -387,54 +680,68 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
             "resync" => {
                 let device = ev.device.as_ref().ok_or("resync missing device")?;
                 let target_version = ev.target_dr_version.ok_or("resync missing target")?;
-                let before_delta = {
-                    let (_, _, d) = current_dr_stats(&devices);
-                    d
-                };
-                {
-                    let dev = devices
-                        .get_mut(device)
-                        .ok_or_else(|| format!("unknown device {device}"))?;
-                    recovery_attempts += 1;
-                    if target_version < dev.dr_version {
-                        let rollback = dev.dr_version - target_version;
-                        if rollback > max_rollback {
-                            max_rollback = rollback;
-                        }
-                    }
-                    dev.dr_version = target_version;
-                    if let Some(hash) = ev.state_hash.clone() {
-                        dev.state_hash = Some(hash);
-                    }
-                }
-                let after_delta = {
-                    let (_, _, d) = current_dr_stats(&devices);
-                    d
-                };
-                if after_delta == 0 {
-                    successful_recoveries += 1;
-                } else if after_delta < before_delta {
-                    notes.push(format!("resync on {} reduced divergence", device));
-                } else {
-                    failed_recoveries += 1;
+                if !devices.contains_key(device) {
+                    return Err(format!("unknown device {device}").into());
                 }
+                // Enqueue rather than apply instantly: the attempt is made
+                // (and, if it doesn't converge, rescheduled with backoff)
+                // the next time `drain_ready_resyncs` runs at or after `ev.t`.
+                // The authored target only pins the device's own component;
+                // unlike the auto-heal frontier merge below it can still
+                // express a deliberate rollback.
+                let mut target_vector = HashMap::new();
+                target_vector.insert(device.clone(), target_version as i64);
+                pending_resyncs.insert(
+                    device.clone(),
+                    PendingResync {
+                        target_vector,
+                        kind: ResyncKind::Authored,
+                        attempts: 0,
+                        next_attempt_t: ev.t,
+                    },
+                );
             }
             _ => return Err(format!("unsupported event {}", ev.event).into()),
         }
 
-        let (min_ver, _, dr_delta) = current_dr_stats(&devices);
-        dr_integral += dr_delta;
+        let (gap, concurrent_pairs, _) = fleet_stats(&devices);
+        dr_integral += gap;
         dr_samples += 1;
-        if dr_delta > max_dr_delta {
-            max_dr_delta = dr_delta;
+        if gap > max_causal_gap {
+            max_causal_gap = gap;
+        }
+        if concurrent_pairs > max_concurrent_forks {
+            max_concurrent_forks = concurrent_pairs;
+        }
+        if concurrent_pairs > 0 && !contains(&errors, "CONCURRENT_FORK") {
+            errors.push("CONCURRENT_FORK".into());
         }
 
-        let divergence_active = dr_delta > 0;
+        let divergence_active = gap > 0 || concurrent_pairs > 0;
         if divergence_active && divergence_start.is_none() {
             divergence_start = Some(ev.t);
             if detection_time.is_none() {
                 detection_time = Some(ev.t);
             }
+            // Auto-heal: enqueue a backoff resync for every device not yet
+            // at the fleet's causal frontier, independent of any explicit
+            // authored `resync` event. `target_vector` is only a snapshot
+            // of the frontier as of *now*; `drain_ready_resyncs` merges it
+            // component-wise against the device's live vector at drain
+            // time (rather than overwriting), so a component the device
+            // has since learned elsewhere never gets regressed by a stale
+            // snapshot sitting in the backoff queue.
+            let front = frontier(&devices);
+            for (device_id, dev) in devices.iter() {
+                if !vector_eq(&dev.vector, &front) {
+                    pending_resyncs.entry(device_id.clone()).or_insert(PendingResync {
+                        target_vector: front.clone(),
+                        kind: ResyncKind::AutoHeal,
+                        attempts: 0,
+                        next_attempt_t: ev.t + base_delay_ms,
+                    });
+                }
+            }
         }
         if divergence_active && !contains(&errors, "DIVERGENCE_DETECTED") {
             errors.push("DIVERGENCE_DETECTED".into());
@@ -443,9 +750,9 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
             recovery_time = Some(ev.t);
         }
 
-        let diverged = devices.values().filter(|d| d.dr_version != min_ver).count();
-        if diverged as i32 > max_diverged_count {
-            max_diverged_count = diverged as i32;
+        let (_, _, diverged) = fleet_stats(&devices);
+        if diverged > max_diverged_count {
+            max_diverged_count = diverged;
         }
         let cr = clock_range(&devices);
         if cr > max_clock_skew {
@@ -453,6 +760,36 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
         }
     }
 
+    // Drain any resyncs still scheduled past the last timeline event so a
+    // budget-exceeded or eventual convergence isn't silently left dangling.
+    let mut drain_cursor = events.last().map(|e| e.t).unwrap_or(0);
+    let mut drain_guard = 0;
+    while !pending_resyncs.is_empty() && drain_guard < 1000 {
+        let next_t = pending_resyncs
+            .values()
+            .map(|p| p.next_attempt_t)
+            .min()
+            .unwrap_or(drain_cursor);
+        drain_cursor = next_t.max(drain_cursor);
+        drain_ready_resyncs(
+            drain_cursor,
+            &mut pending_resyncs,
+            &mut devices,
+            base_delay_ms,
+            max_delay_ms,
+            max_resync_attempts,
+            &mut scheduled_resyncs,
+            &mut total_backoff_ms,
+            &mut recovery_attempts,
+            &mut successful_recoveries,
+            &mut failed_recoveries,
+            &mut max_rollback,
+            &mut errors,
+            &mut recovery_time,
+        );
+        drain_guard += 1;
+    }
+
     if divergence_start.is_none() && !errors.is_empty() {
         let t = events.first().map(|e| e.t).unwrap_or(0);
         divergence_start = Some(t);
@@ -461,8 +798,8 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
         }
     }
 
-    let (_, _, end_delta) = current_dr_stats(&devices);
-    let residual_divergence = end_delta > 0;
+    let (end_gap, end_concurrent, _) = fleet_stats(&devices);
+    let residual_divergence = end_gap > 0 || end_concurrent > 0;
 
     let detection_ms = detection_time.and_then(|dt| divergence_start.map(|ds| (dt - ds).max(0)));
     let recovery_ms = recovery_time.and_then(|rt| detection_time.map(|dt| (rt - dt).max(0)));
@@ -493,14 +830,15 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
         add_error(&mut errors, &mut detection_time, "OUT_OF_ORDER", None);
     }
 
-    let (min_for_metrics, _, _) = current_dr_stats(&devices);
-    let diverged_count = devices
-        .values()
-        .filter(|d| d.dr_version != min_for_metrics)
-        .count() as i32;
+    let (_, _, diverged_count) = fleet_stats(&devices);
 
     let metrics = serde_json::json!({
-        "max_dr_version_delta": max_dr_delta,
+        // Kept under its historical name, now derived from the largest
+        // dominated version-vector gap, so `DrDeltaDetector`'s SLA check
+        // keeps working unchanged.
+        "max_dr_version_delta": max_causal_gap,
+        "max_causal_gap": max_causal_gap,
+        "concurrent_fork_count": max_concurrent_forks,
         "avg_dr_version_delta": avg_dr,
         "max_clock_skew_ms": max_clock_skew,
         "diverged_device_count": diverged_count,
@@ -516,6 +854,11 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
         "failed_recoveries": failed_recoveries,
         "max_rollback_events": max_rollback,
         "residual_divergence": residual_divergence,
+        "scheduled_resyncs": scheduled_resyncs,
+        "total_backoff_ms": total_backoff_ms,
+        "max_resync_attempts": max_resync_attempts,
+        "detection_ms": detection_ms,
+        "recovery_ms": recovery_ms,
     });
 
     Ok(SimulationResult {
@@ -528,79 +871,698 @@ fn simulate(s: &Scenario) -> Result<SimulationResult, Box<dyn Error>> {
     })
 }
 
-fn eval_expectations(exp: &Expectations, res: &SimulationResult) -> (String, Vec<String>) {
-    let mut failures = Vec::new();
-    if res.detection != exp.detected {
-        failures.push("detection_mismatch".into());
+/// How fatal a `Diagnostic` is. Only `Error` flips a scenario to `fail`;
+/// `Warning`/`Info` are surfaced in `ScenarioSummary` but don't affect the
+/// exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// One detector's finding: a stable `code` (matches the legacy bare failure
+/// strings, e.g. `"clock_skew_exceeded"`), a human `message`, the offending
+/// metric `value`, and a `severity`.
+#[derive(Debug, Clone, Serialize)]
+struct Diagnostic {
+    code: String,
+    message: String,
+    value: serde_json::Value,
+    severity: Severity,
+}
+
+impl Diagnostic {
+    fn error(code: &str, message: impl Into<String>, value: serde_json::Value) -> Self {
+        Self {
+            code: code.to_string(),
+            message: message.into(),
+            value,
+            severity: Severity::Error,
+        }
     }
-    if exp.detected {
-        match res.detection_ms {
-            None => failures.push("missing_detection_ms".into()),
-            Some(ms) => {
-                if exp.max_detection_ms > 0 && ms > exp.max_detection_ms {
-                    failures.push("detection_sla".into());
+}
+
+/// A single expectation check against a `SimulationResult`. Each detector
+/// owns one divergence class and is registered in `detectors()`; adding a
+/// new class of check means adding a new `Detector`, not editing a
+/// monolithic function.
+trait Detector {
+    fn check(&self, exp: &Expectations, res: &SimulationResult) -> Vec<Diagnostic>;
+}
+
+struct DetectionSlaDetector;
+impl Detector for DetectionSlaDetector {
+    fn check(&self, exp: &Expectations, res: &SimulationResult) -> Vec<Diagnostic> {
+        let mut out = Vec::new();
+        if res.detection != exp.detected {
+            out.push(Diagnostic::error(
+                "detection_mismatch",
+                format!("expected detected={}, observed={}", exp.detected, res.detection),
+                serde_json::json!(res.detection),
+            ));
+        }
+        if exp.detected {
+            match res.detection_ms {
+                None => out.push(Diagnostic::error(
+                    "missing_detection_ms",
+                    "divergence was expected but no detection_ms was recorded",
+                    serde_json::Value::Null,
+                )),
+                Some(ms) => {
+                    if exp.max_detection_ms > 0 && ms > exp.max_detection_ms {
+                        out.push(Diagnostic::error(
+                            "detection_sla",
+                            format!("detection took {}ms, budget was {}ms", ms, exp.max_detection_ms),
+                            serde_json::json!(ms),
+                        ));
+                    }
                 }
             }
+        } else if let Some(ms) = res.detection_ms {
+            if ms != 0 {
+                out.push(Diagnostic::error(
+                    "unexpected_detection_ms",
+                    format!("detection was not expected but detection_ms={}", ms),
+                    serde_json::json!(ms),
+                ));
+            }
         }
-    } else if let Some(ms) = res.detection_ms {
-        if ms != 0 {
-            failures.push("unexpected_detection_ms".into());
-        }
+        out
     }
+}
 
-    if exp.healing_required {
-        match res.recovery_ms {
-            None => failures.push("missing_recovery_ms".into()),
-            Some(ms) => {
-                if exp.max_recovery_ms > 0 && ms > exp.max_recovery_ms {
-                    failures.push("recovery_sla".into());
+struct RecoverySlaDetector;
+impl Detector for RecoverySlaDetector {
+    fn check(&self, exp: &Expectations, res: &SimulationResult) -> Vec<Diagnostic> {
+        let mut out = Vec::new();
+        if exp.healing_required {
+            match res.recovery_ms {
+                None => out.push(Diagnostic::error(
+                    "missing_recovery_ms",
+                    "healing was required but no recovery_ms was recorded",
+                    serde_json::Value::Null,
+                )),
+                Some(ms) => {
+                    if exp.max_recovery_ms > 0 && ms > exp.max_recovery_ms {
+                        out.push(Diagnostic::error(
+                            "recovery_sla",
+                            format!("recovery took {}ms, budget was {}ms", ms, exp.max_recovery_ms),
+                            serde_json::json!(ms),
+                        ));
+                    }
                 }
             }
-        }
-        if !exp.residual_divergence_allowed {
-            if res.metrics["residual_divergence"]
-                .as_bool()
-                .unwrap_or(false)
+            if !exp.residual_divergence_allowed
+                && res.metrics["residual_divergence"].as_bool().unwrap_or(false)
             {
-                failures.push("residual_divergence".into());
+                out.push(Diagnostic::error(
+                    "residual_divergence",
+                    "residual divergence remained after healing but was not allowed",
+                    serde_json::json!(true),
+                ));
             }
         }
+        out
     }
+}
 
-    if res.metrics["max_dr_version_delta"].as_i64().unwrap_or(0) as i32 > exp.max_dr_version_delta {
-        failures.push("dr_delta_exceeded".into());
+struct DrDeltaDetector;
+impl Detector for DrDeltaDetector {
+    fn check(&self, exp: &Expectations, res: &SimulationResult) -> Vec<Diagnostic> {
+        let delta = res.metrics["max_dr_version_delta"].as_i64().unwrap_or(0) as i32;
+        if delta > exp.max_dr_version_delta {
+            vec![Diagnostic::error(
+                "dr_delta_exceeded",
+                format!("max dr_version delta {} exceeded budget {}", delta, exp.max_dr_version_delta),
+                serde_json::json!(delta),
+            )]
+        } else {
+            Vec::new()
+        }
     }
-    if res.metrics["max_clock_skew_ms"].as_i64().unwrap_or(0) as i32 > exp.max_clock_skew_ms {
-        failures.push("clock_skew_exceeded".into());
+}
+
+struct ClockSkewDetector;
+impl Detector for ClockSkewDetector {
+    fn check(&self, exp: &Expectations, res: &SimulationResult) -> Vec<Diagnostic> {
+        let skew = res.metrics["max_clock_skew_ms"].as_i64().unwrap_or(0) as i32;
+        if skew > exp.max_clock_skew_ms {
+            vec![Diagnostic::error(
+                "clock_skew_exceeded",
+                format!("max clock skew {}ms exceeded budget {}ms", skew, exp.max_clock_skew_ms),
+                serde_json::json!(skew),
+            )]
+        } else {
+            Vec::new()
+        }
     }
-    if res.metrics["message_loss_rate"].as_f64().unwrap_or(0.0) > exp.allow_message_loss_rate {
-        failures.push("message_loss_rate".into());
+}
+
+struct LossOutOfOrderDetector;
+impl Detector for LossOutOfOrderDetector {
+    fn check(&self, exp: &Expectations, res: &SimulationResult) -> Vec<Diagnostic> {
+        let mut out = Vec::new();
+        let loss = res.metrics["message_loss_rate"].as_f64().unwrap_or(0.0);
+        if loss > exp.allow_message_loss_rate {
+            out.push(Diagnostic::error(
+                "message_loss_rate",
+                format!("message loss rate {:.4} exceeded budget {:.4}", loss, exp.allow_message_loss_rate),
+                serde_json::json!(loss),
+            ));
+        }
+        let ooo = res.metrics["out_of_order_rate"].as_f64().unwrap_or(0.0);
+        if ooo > exp.allow_out_of_order_rate {
+            out.push(Diagnostic::error(
+                "out_of_order_rate",
+                format!("out-of-order rate {:.4} exceeded budget {:.4}", ooo, exp.allow_out_of_order_rate),
+                serde_json::json!(ooo),
+            ));
+        }
+        out
+    }
+}
+
+struct RollbackDetector;
+impl Detector for RollbackDetector {
+    fn check(&self, exp: &Expectations, res: &SimulationResult) -> Vec<Diagnostic> {
+        let rollback = res.metrics["max_rollback_events"].as_i64().unwrap_or(0) as i32;
+        if rollback > exp.max_rollback_events {
+            vec![Diagnostic::error(
+                "rollback_exceeded",
+                format!("max rollback {} exceeded budget {}", rollback, exp.max_rollback_events),
+                serde_json::json!(rollback),
+            )]
+        } else {
+            Vec::new()
+        }
     }
-    if res.metrics["out_of_order_rate"].as_f64().unwrap_or(0.0) > exp.allow_out_of_order_rate {
-        failures.push("out_of_order_rate".into());
+}
+
+struct MissingErrorCategoriesDetector;
+impl Detector for MissingErrorCategoriesDetector {
+    fn check(&self, exp: &Expectations, res: &SimulationResult) -> Vec<Diagnostic> {
+        let missing: Vec<String> = exp
+            .expected_error_categories
+            .iter()
+            .filter(|code| !res.errors.iter().any(|e| e == *code))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            vec![Diagnostic::error(
+                "missing_error_categories",
+                format!("missing expected error categories: {}", missing.join(", ")),
+                serde_json::json!(missing),
+            )]
+        } else {
+            Vec::new()
+        }
     }
-    if res.metrics["max_rollback_events"].as_i64().unwrap_or(0) as i32 > exp.max_rollback_events {
-        failures.push("rollback_exceeded".into());
+}
+
+fn detectors() -> Vec<Box<dyn Detector>> {
+    vec![
+        Box::new(DetectionSlaDetector),
+        Box::new(RecoverySlaDetector),
+        Box::new(DrDeltaDetector),
+        Box::new(ClockSkewDetector),
+        Box::new(LossOutOfOrderDetector),
+        Box::new(RollbackDetector),
+        Box::new(MissingErrorCategoriesDetector),
+    ]
+}
+
+/// Runs every registered `Detector`, downgrades any code listed in
+/// `exp.warn_only` to `Warning`, then splits the resulting diagnostics into
+/// `(status, failures, warnings, diagnostics)`. `status` is `"fail"` only
+/// when an `Error`-level diagnostic survives the downgrade.
+fn eval_expectations(
+    exp: &Expectations,
+    res: &SimulationResult,
+) -> (String, Vec<String>, Vec<String>, Vec<Diagnostic>) {
+    let mut diagnostics: Vec<Diagnostic> =
+        detectors().iter().flat_map(|d| d.check(exp, res)).collect();
+
+    for diag in diagnostics.iter_mut() {
+        if exp.warn_only.iter().any(|code| code == &diag.code) {
+            diag.severity = Severity::Warning;
+        }
     }
 
-    let missing: Vec<String> = exp
-        .expected_error_categories
+    let failures: Vec<String> = diagnostics
         .iter()
-        .filter(|code| !res.errors.iter().any(|e| e == *code))
-        .cloned()
+        .filter(|d| d.severity == Severity::Error)
+        .map(|d| d.code.clone())
         .collect();
-    if !missing.is_empty() {
-        failures.push("missing_error_categories".into());
+    let warnings: Vec<String> = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Warning)
+        .map(|d| d.code.clone())
+        .collect();
+
+    let status = if failures.is_empty() { "pass" } else { "fail" };
+    (status.to_string(), failures, warnings, diagnostics)
+}
+
+/// Escapes a Prometheus label value: backslash, double-quote, and newline
+/// are the only characters the exposition format requires escaping.
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Renders every scenario's `metrics` map into Prometheus text exposition
+/// format, one gauge per metric key labeled with `scenario_id`/`status`/
+/// `corpus`, plus aggregate `scenario_total`/`scenario_passed`/
+/// `scenario_failed` counters. Written alongside the JSON summary so CI can
+/// scrape or push it instead of re-parsing JSON for trend tracking.
+fn render_prometheus(summary: &Summary) -> String {
+    let mut out = String::new();
+    let corpus = escape_label(&summary.corpus);
+
+    out.push_str("# HELP foxwhisper_scenario_total Total scenarios in the corpus.\n");
+    out.push_str("# TYPE foxwhisper_scenario_total counter\n");
+    out.push_str(&format!("foxwhisper_scenario_total{{corpus=\"{corpus}\"}} {}\n", summary.total));
+    out.push_str("# HELP foxwhisper_scenario_passed Scenarios that passed expectations.\n");
+    out.push_str("# TYPE foxwhisper_scenario_passed counter\n");
+    out.push_str(&format!("foxwhisper_scenario_passed{{corpus=\"{corpus}\"}} {}\n", summary.passed));
+    out.push_str("# HELP foxwhisper_scenario_failed Scenarios that failed expectations.\n");
+    out.push_str("# TYPE foxwhisper_scenario_failed counter\n");
+    out.push_str(&format!("foxwhisper_scenario_failed{{corpus=\"{corpus}\"}} {}\n", summary.failed));
+
+    // Every metric key that appears anywhere, in first-seen order, so each
+    // gauge gets exactly one HELP/TYPE header regardless of which scenarios
+    // happen to carry it.
+    let mut metric_names: Vec<String> = Vec::new();
+    for scenario in &summary.scenarios {
+        if let Some(obj) = scenario.metrics.as_object() {
+            for key in obj.keys() {
+                if !metric_names.contains(key) {
+                    metric_names.push(key.clone());
+                }
+            }
+        }
     }
 
-    if failures.is_empty() {
-        ("pass".into(), failures)
+    for name in &metric_names {
+        let gauge = format!("foxwhisper_{name}");
+        out.push_str(&format!(
+            "# HELP {gauge} Scenario metric `{name}` from the device-desync harness.\n"
+        ));
+        out.push_str(&format!("# TYPE {gauge} gauge\n"));
+        for scenario in &summary.scenarios {
+            let rendered = match scenario.metrics.get(name) {
+                Some(serde_json::Value::Number(n)) => n.as_f64(),
+                Some(serde_json::Value::Bool(b)) => Some(if *b { 1.0 } else { 0.0 }),
+                _ => None,
+            };
+            if let Some(v) = rendered {
+                out.push_str(&format!(
+                    "{gauge}{{scenario_id=\"{}\",status=\"{}\",corpus=\"{corpus}\"}} {v}\n",
+                    escape_label(&scenario.scenario_id),
+                    escape_label(&scenario.status),
+                ));
+            }
+        }
+    }
+
+    out
+}
+
+/// One finding from `validate_corpus`: a scenario/event location, a stable
+/// `code` (`"missing_field"`, `"unknown_event"`, `"unknown_device"`,
+/// `"non_monotonic_t"`, `"unreachable_recv"`), and a human `message`. All
+/// violations in a corpus are collected before reporting, rather than
+/// bailing out on the first one the way a mid-`simulate` `Err` does.
+#[derive(Debug, Serialize)]
+struct CorpusViolation {
+    scenario_id: String,
+    event_index: usize,
+    t: i32,
+    code: String,
+    message: String,
+}
+
+/// Fields an `Event` of this type must carry: the ones `simulate`'s event
+/// loop fetches with `ok_or(...)`, plus a non-empty `to` on `send`/`replay`,
+/// which `simulate` itself would default to no recipients.
+fn required_fields(event: &str) -> &'static [&'static str] {
+    match event {
+        "send" => &["from", "to", "msg_id"],
+        "recv" => &["device", "msg_id"],
+        "drop" => &["msg_id"],
+        "replay" => &["from", "to", "msg_id"],
+        "backup_restore" => &["device", "dr_version"],
+        "clock_skew" => &["device", "delta_ms"],
+        "resync" => &["device", "target_dr_version"],
+        _ => &[],
+    }
+}
+
+fn has_field(ev: &Event, field: &str) -> bool {
+    match field {
+        "from" => ev.from.is_some(),
+        "to" => ev.to.as_ref().is_some_and(|v| !v.is_empty()),
+        "msg_id" => ev.msg_id.is_some(),
+        "device" => ev.device.is_some(),
+        "dr_version" => ev.dr_version.is_some(),
+        "delta_ms" => ev.delta_ms.is_some(),
+        "target_dr_version" => ev.target_dr_version.is_some(),
+        _ => true,
+    }
+}
+
+const KNOWN_EVENTS: &[&str] = &[
+    "send",
+    "recv",
+    "drop",
+    "replay",
+    "backup_restore",
+    "clock_skew",
+    "resync",
+];
+
+/// Statically checks one scenario's timeline the way `simulate` implicitly
+/// would, but collects every violation instead of stopping at the first.
+fn validate_scenario(s: &Scenario) -> Vec<CorpusViolation> {
+    let mut out = Vec::new();
+    let device_ids: HashSet<&str> = s.devices.iter().map(|d| d.device_id.as_str()).collect();
+    let mut sent_msg_ids: HashSet<&str> = HashSet::new();
+    let mut last_t = i32::MIN;
+
+    let violation = |event_index: usize, t: i32, code: &str, message: String| CorpusViolation {
+        scenario_id: s.scenario_id.clone(),
+        event_index,
+        t,
+        code: code.to_string(),
+        message,
+    };
+
+    for (i, ev) in s.timeline.iter().enumerate() {
+        if !KNOWN_EVENTS.contains(&ev.event.as_str()) {
+            out.push(violation(i, ev.t, "unknown_event", format!("unknown event type `{}`", ev.event)));
+        }
+        for field in required_fields(&ev.event) {
+            if !has_field(ev, field) {
+                out.push(violation(
+                    i,
+                    ev.t,
+                    "missing_field",
+                    format!("`{}` event missing required field `{}`", ev.event, field),
+                ));
+            }
+        }
+        for device_ref in [ev.from.as_deref(), ev.device.as_deref()].into_iter().flatten() {
+            if !device_ids.contains(device_ref) {
+                out.push(violation(
+                    i,
+                    ev.t,
+                    "unknown_device",
+                    format!("references undeclared device `{device_ref}`"),
+                ));
+            }
+        }
+        for list in [ev.to.as_ref(), ev.targets.as_ref()].into_iter().flatten() {
+            for device_ref in list {
+                if !device_ids.contains(device_ref.as_str()) {
+                    out.push(violation(
+                        i,
+                        ev.t,
+                        "unknown_device",
+                        format!("references undeclared device `{device_ref}`"),
+                    ));
+                }
+            }
+        }
+        if ev.t < last_t {
+            out.push(violation(
+                i,
+                ev.t,
+                "non_monotonic_t",
+                format!("t={} authored after a later t={last_t}", ev.t),
+            ));
+        }
+        last_t = last_t.max(ev.t);
+
+        match ev.event.as_str() {
+            "send" | "replay" => {
+                if let Some(msg_id) = &ev.msg_id {
+                    sent_msg_ids.insert(msg_id.as_str());
+                }
+            }
+            "recv" => {
+                if let Some(msg_id) = &ev.msg_id {
+                    if !sent_msg_ids.contains(msg_id.as_str()) {
+                        out.push(violation(
+                            i,
+                            ev.t,
+                            "unreachable_recv",
+                            format!("`recv` of msg_id `{msg_id}` with no prior send/replay in this scenario"),
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+fn validate_corpus(scenarios: &[Scenario]) -> Vec<CorpusViolation> {
+    scenarios.iter().flat_map(validate_scenario).collect()
+}
+
+/// One row of a flattened, spreadsheet-friendly corpus table. `row_type`
+/// (`"device"` / `"event"` / `"expectations"`) disambiguates which of a
+/// `Scenario`'s three nested sections this row contributes to; rows sharing
+/// a `scenario_id` are grouped back into one `Scenario` by `convert_flat`.
+/// Multi-value fields (`to`, `targets`, `expected_error_categories`, `tags`)
+/// are authored as a delimited string since a spreadsheet cell can't hold a
+/// JSON array.
+#[derive(Debug, Deserialize)]
+struct FlatRow {
+    scenario_id: String,
+    row_type: String,
+    #[serde(default)]
+    tags: Option<String>,
+    #[serde(default)]
+    device_id: Option<String>,
+    #[serde(default)]
+    dr_version: Option<i32>,
+    #[serde(default)]
+    clock_ms: Option<i32>,
+    #[serde(default)]
+    state_hash: Option<String>,
+    #[serde(default)]
+    t: Option<i32>,
+    #[serde(default)]
+    event: Option<String>,
+    #[serde(default)]
+    from: Option<String>,
+    #[serde(default)]
+    to: Option<String>,
+    #[serde(default)]
+    msg_id: Option<String>,
+    #[serde(default)]
+    device: Option<String>,
+    #[serde(default)]
+    apply_dr_version: Option<i32>,
+    #[serde(default)]
+    targets: Option<String>,
+    #[serde(default)]
+    delta_ms: Option<i32>,
+    #[serde(default)]
+    target_dr_version: Option<i32>,
+    #[serde(default)]
+    detected: Option<bool>,
+    #[serde(default)]
+    max_detection_ms: Option<i32>,
+    #[serde(default)]
+    max_recovery_ms: Option<i32>,
+    #[serde(default)]
+    healing_required: Option<bool>,
+    #[serde(default)]
+    residual_divergence_allowed: Option<bool>,
+    #[serde(default)]
+    max_dr_version_delta: Option<i32>,
+    #[serde(default)]
+    max_clock_skew_ms: Option<i32>,
+    #[serde(default)]
+    allow_message_loss_rate: Option<f64>,
+    #[serde(default)]
+    allow_out_of_order_rate: Option<f64>,
+    #[serde(default)]
+    expected_error_categories: Option<String>,
+    #[serde(default)]
+    max_rollback_events: Option<i32>,
+}
+
+/// Splits a `|`-delimited spreadsheet cell into a list, dropping empty
+/// entries (so an absent cell or a trailing separator doesn't produce a
+/// spurious `""` element).
+fn split_list(raw: &Option<String>) -> Vec<String> {
+    raw.as_deref()
+        .map(|s| s.split('|').map(|x| x.trim().to_string()).filter(|x| !x.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+fn default_expectations() -> Expectations {
+    Expectations {
+        detected: false,
+        max_detection_ms: 0,
+        max_recovery_ms: 0,
+        healing_required: false,
+        residual_divergence_allowed: false,
+        max_dr_version_delta: 0,
+        max_clock_skew_ms: 0,
+        allow_message_loss_rate: 0.0,
+        allow_out_of_order_rate: 0.0,
+        expected_error_categories: Vec::new(),
+        max_rollback_events: 0,
+        warn_only: Vec::new(),
+        resync_base_delay_ms: 0,
+        resync_max_delay_ms: 0,
+        max_resync_attempts: 0,
+    }
+}
+
+/// Normalizes a flat, spreadsheet-friendly table (one row per device,
+/// event, or expectations fact, grouped by `scenario_id` and disambiguated
+/// by `row_type`) into the canonical nested `Scenario` shape `simulate`
+/// expects.
+fn convert_flat(rows: Vec<FlatRow>) -> Vec<Scenario> {
+    let mut order: Vec<String> = Vec::new();
+    let mut devices: HashMap<String, Vec<Device>> = HashMap::new();
+    let mut timelines: HashMap<String, Vec<Event>> = HashMap::new();
+    let mut expectations: HashMap<String, Expectations> = HashMap::new();
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+
+    for row in rows {
+        if !order.contains(&row.scenario_id) {
+            order.push(row.scenario_id.clone());
+        }
+        if let Some(raw_tags) = &row.tags {
+            tags.entry(row.scenario_id.clone())
+                .or_default()
+                .extend(raw_tags.split(',').map(|x| x.trim().to_string()).filter(|x| !x.is_empty()));
+        }
+        match row.row_type.as_str() {
+            "device" => {
+                if let Some(device_id) = row.device_id.clone() {
+                    devices.entry(row.scenario_id.clone()).or_default().push(Device {
+                        device_id,
+                        dr_version: row.dr_version.unwrap_or(0),
+                        clock_ms: row.clock_ms.unwrap_or(0),
+                        state_hash: row.state_hash.clone(),
+                    });
+                }
+            }
+            "event" => {
+                if let Some(event) = row.event.clone() {
+                    let to = split_list(&row.to);
+                    let targets = split_list(&row.targets);
+                    timelines.entry(row.scenario_id.clone()).or_default().push(Event {
+                        t: row.t.unwrap_or(0),
+                        event,
+                        from: row.from.clone(),
+                        to: (!to.is_empty()).then_some(to),
+                        msg_id: row.msg_id.clone(),
+                        device: row.device.clone(),
+                        apply_dr_version: row.apply_dr_version,
+                        state_hash: row.state_hash.clone(),
+                        dr_version: row.dr_version,
+                        targets: (!targets.is_empty()).then_some(targets),
+                        delta_ms: row.delta_ms,
+                        target_dr_version: row.target_dr_version,
+                    });
+                }
+            }
+            "expectations" => {
+                expectations.insert(
+                    row.scenario_id.clone(),
+                    Expectations {
+                        detected: row.detected.unwrap_or(false),
+                        max_detection_ms: row.max_detection_ms.unwrap_or(0),
+                        max_recovery_ms: row.max_recovery_ms.unwrap_or(0),
+                        healing_required: row.healing_required.unwrap_or(false),
+                        residual_divergence_allowed: row.residual_divergence_allowed.unwrap_or(false),
+                        max_dr_version_delta: row.max_dr_version_delta.unwrap_or(0),
+                        max_clock_skew_ms: row.max_clock_skew_ms.unwrap_or(0),
+                        allow_message_loss_rate: row.allow_message_loss_rate.unwrap_or(0.0),
+                        allow_out_of_order_rate: row.allow_out_of_order_rate.unwrap_or(0.0),
+                        expected_error_categories: split_list(&row.expected_error_categories),
+                        max_rollback_events: row.max_rollback_events.unwrap_or(0),
+                        warn_only: Vec::new(),
+                        resync_base_delay_ms: 0,
+                        resync_max_delay_ms: 0,
+                        max_resync_attempts: 0,
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+
+    order
+        .into_iter()
+        .map(|scenario_id| Scenario {
+            tags: tags.remove(&scenario_id).unwrap_or_default(),
+            devices: devices.remove(&scenario_id).unwrap_or_default(),
+            timeline: timelines.remove(&scenario_id).unwrap_or_default(),
+            expectations: expectations.remove(&scenario_id).unwrap_or_else(default_expectations),
+            scenario_id,
+        })
+        .collect()
+}
+
+/// `validate <path>` statically checks an arbitrary corpus and reports every
+/// violation at once (rather than the first `Err` `simulate` would hit).
+fn run_validate(path: &str) -> Result<(), Box<dyn Error>> {
+    let scenarios: Vec<Scenario> = load_json(path)?;
+    let violations = validate_corpus(&scenarios);
+    for v in &violations {
+        eprintln!("❌ [{} event #{} t={}] {}: {}", v.scenario_id, v.event_index, v.t, v.code, v.message);
+    }
+    write_json("rust_device_desync_corpus_validation.json", &violations)?;
+    if violations.is_empty() {
+        println!("✅ {} scenario(s) in {path} have no structural violations", scenarios.len());
+        Ok(())
     } else {
-        ("fail".into(), failures)
+        eprintln!("❌ {} violation(s) found in {path}", violations.len());
+        std::process::exit(1);
     }
 }
 
+/// `convert <input path> <output filename>` normalizes a flat tabular
+/// corpus into the canonical nested `Scenario` JSON `simulate` expects,
+/// writing the result under `results/`.
+fn run_convert(input_path: &str, output_filename: &str) -> Result<(), Box<dyn Error>> {
+    let rows: Vec<FlatRow> = load_json(input_path)?;
+    let row_count = rows.len();
+    let scenarios = convert_flat(rows);
+    write_json(output_filename, &scenarios)?;
+    println!(
+        "✅ converted {row_count} row(s) into {} scenario(s) -> results/{output_filename}",
+        scenarios.len()
+    );
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(|s| s.as_str()) {
+        Some("validate") => {
+            let path = args.get(2).map(|s| s.as_str()).unwrap_or("tests/common/adversarial/device_desync.json");
+            return run_validate(path);
+        }
+        Some("convert") => {
+            let input_path = args.get(2).ok_or("convert requires an input path")?;
+            let output_filename = args.get(3).map(|s| s.as_str()).unwrap_or("converted_device_desync.json");
+            return run_convert(input_path, output_filename);
+        }
+        _ => {}
+    }
+
     let corpus_path = "tests/common/adversarial/device_desync.json";
     let scenarios: Vec<Scenario> = load_json(corpus_path)?;
     let mut summary = Summary {
@@ -614,7 +1576,8 @@ fn main() -> Result<(), Box<dyn Error>> {
     for scenario in scenarios.iter() {
         match simulate(scenario) {
             Ok(res) => {
-                let (status, failures) = eval_expectations(&scenario.expectations, &res);
+                let (status, failures, warnings, diagnostics) =
+                    eval_expectations(&scenario.expectations, &res);
                 if status == "pass" {
                     summary.passed += 1;
                 } else {
@@ -624,6 +1587,8 @@ fn main() -> Result<(), Box<dyn Error>> {
                     scenario_id: scenario.scenario_id.clone(),
                     status,
                     failures,
+                    warnings,
+                    diagnostics,
                     errors: res.errors,
                     metrics: res.metrics,
                     notes: res.notes,
@@ -635,6 +1600,8 @@ fn main() -> Result<(), Box<dyn Error>> {
                     scenario_id: scenario.scenario_id.clone(),
                     status: "fail".into(),
                     failures: vec![e.to_string()],
+                    warnings: vec![],
+                    diagnostics: vec![],
                     errors: vec![e.to_string()],
                     metrics: serde_json::json!({}),
                     notes: vec![],
@@ -644,6 +1611,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
 
     write_json("rust_device_desync_summary.json", &summary)?;
+    write_text("rust_device_desync_summary.prom", &render_prometheus(&summary))?;
 
     if summary.failed > 0 {
         eprintln!("❌ {} scenario(s) failed", summary.failed);
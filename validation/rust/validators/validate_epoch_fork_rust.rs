@@ -1,3 +1,4 @@
+use sha2::{Digest, Sha256};
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::cmp::Ordering;
@@ -7,6 +8,89 @@ use std::fs;
 
 mod util;
 
+/// The digest a corpus's commitments (`eare_hash`, `membership_digest`) were
+/// generated with. Pluggable per scenario via `Scenario::hash_algorithm` so
+/// older sha256 vectors and newer blake3 ones can coexist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HashAlgorithm {
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "blake3" => HashAlgorithm::Blake3,
+            _ => HashAlgorithm::Sha256,
+        }
+    }
+
+    fn digest_hex(&self, parts: &[&[u8]]) -> String {
+        match self {
+            HashAlgorithm::Sha256 => {
+                let mut hasher = Sha256::new();
+                for part in parts {
+                    hasher.update(part);
+                }
+                hex::encode(hasher.finalize())
+            }
+            HashAlgorithm::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                for part in parts {
+                    hasher.update(part);
+                }
+                hex::encode(hasher.finalize().as_bytes())
+            }
+        }
+    }
+}
+
+/// Domain-separated Merkle leaf hash: `H("leaf" || id)`.
+fn merkle_leaf(hash_algo: HashAlgorithm, id: &str) -> String {
+    hash_algo.digest_hex(&[b"leaf", id.as_bytes()])
+}
+
+/// Domain-separated Merkle interior hash: `H("node" || left || right)`.
+fn merkle_node(hash_algo: HashAlgorithm, left: &str, right: &str) -> String {
+    hash_algo.digest_hex(&[b"node", left.as_bytes(), right.as_bytes()])
+}
+
+/// Merkle root over sorted `participants`, duplicating the last node of an
+/// odd level so every level pairs off cleanly. `None` for an empty list.
+fn merkle_root(hash_algo: HashAlgorithm, participants: &[String]) -> Option<String> {
+    if participants.is_empty() {
+        return None;
+    }
+    let mut sorted = participants.to_vec();
+    sorted.sort();
+
+    let mut level: Vec<String> = sorted.iter().map(|id| merkle_leaf(hash_algo, id)).collect();
+    while level.len() > 1 {
+        if level.len() % 2 == 1 {
+            level.push(level.last().unwrap().clone());
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| merkle_node(hash_algo, &pair[0], &pair[1]))
+            .collect();
+    }
+    level.into_iter().next()
+}
+
+/// Expected `eare_hash` commitment: `H(epoch_id || previous_epoch_hash || membership_digest)`.
+fn expected_eare_hash(
+    hash_algo: HashAlgorithm,
+    epoch_id: i32,
+    previous_epoch_hash: &str,
+    membership_digest: &str,
+) -> String {
+    hash_algo.digest_hex(&[
+        epoch_id.to_string().as_bytes(),
+        previous_epoch_hash.as_bytes(),
+        membership_digest.as_bytes(),
+    ])
+}
+
 #[derive(Debug, Deserialize, Clone, Default)]
 struct GroupContext {
     #[serde(default)]
@@ -119,6 +203,15 @@ struct Scenario {
     graph: Graph,
     event_stream: Vec<Event>,
     expectations: Expectations,
+    /// Selects the head-selection rule `simulate` uses. `"ghost"` picks the
+    /// Greedy-Heaviest-Observed-Sub-Tree head; anything else (including
+    /// absent) keeps the longest-chain heuristic.
+    #[serde(default)]
+    fork_choice: Option<String>,
+    /// Which digest the corpus's `eare_hash`/`membership_digest` commitments
+    /// were generated with. Defaults to sha256.
+    #[serde(default)]
+    hash_algorithm: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -133,6 +226,9 @@ struct Envelope {
     winning_hash: Option<String>,
     winning_node_id: Option<String>,
     messages_dropped: i32,
+    replay_window_span_ms: Option<i64>,
+    replay_window_duplicates: i32,
+    replay_window_underflows: i32,
     healing_actions: Vec<String>,
     errors: Vec<String>,
     false_positives: HashMap<String, i32>,
@@ -159,6 +255,133 @@ fn depth(node_id: &str, nodes: &HashMap<String, EpochNode>) -> i32 {
     d
 }
 
+/// Greedy-Heaviest-Observed-Sub-Tree node ordering, used both to break ties
+/// between sibling subtrees of equal weight and to pick among multiple
+/// candidate roots: higher `epoch_id` wins, then earlier `timestamp_ms`,
+/// then lexically larger `eare_hash`.
+fn ghost_tiebreak_wins(candidate: &EpochNode, incumbent: &EpochNode) -> bool {
+    if candidate.epoch_id != incumbent.epoch_id {
+        return candidate.epoch_id > incumbent.epoch_id;
+    }
+    if candidate.timestamp_ms != incumbent.timestamp_ms {
+        return candidate.timestamp_ms < incumbent.timestamp_ms;
+    }
+    candidate.eare_hash > incumbent.eare_hash
+}
+
+/// Post-order subtree-weight accumulation for one node, memoized across the
+/// whole tree. `seen` guards the same kind of cycle `depth()` guards against
+/// (a node reachable from itself via `parent_id`/child edges), treating a
+/// revisited node's further contribution as zero rather than recursing
+/// forever.
+fn ghost_subtree_weight(
+    node_id: &str,
+    child_map: &HashMap<String, Vec<String>>,
+    own_weight: &HashMap<String, i64>,
+    memo: &mut HashMap<String, i64>,
+    seen: &mut std::collections::HashSet<String>,
+) -> i64 {
+    if let Some(w) = memo.get(node_id) {
+        return *w;
+    }
+    if !seen.insert(node_id.to_string()) {
+        return 0;
+    }
+    let own = *own_weight.get(node_id).unwrap_or(&1);
+    let children_total: i64 = child_map
+        .get(node_id)
+        .map(|kids| {
+            kids.iter()
+                .map(|kid| ghost_subtree_weight(kid, child_map, own_weight, memo, seen))
+                .sum()
+        })
+        .unwrap_or(0);
+    let total = own + children_total;
+    memo.insert(node_id.to_string(), total);
+    seen.remove(node_id);
+    total
+}
+
+/// Picks the GHOST head: builds the tree from `parent_id`, weighs each node
+/// by its observed participant count, computes subtree weights bottom-up,
+/// then descends from the heaviest root always taking the heaviest-subtree
+/// child until a leaf is reached.
+fn ghost_winner(
+    nodes: &HashMap<String, EpochNode>,
+    participant_counts: &HashMap<String, i64>,
+) -> Option<String> {
+    if nodes.is_empty() {
+        return None;
+    }
+
+    let mut child_map: HashMap<String, Vec<String>> = HashMap::new();
+    for n in nodes.values() {
+        if let Some(parent_id) = &n.parent_id {
+            if nodes.contains_key(parent_id) {
+                child_map.entry(parent_id.clone()).or_default().push(n.node_id.clone());
+            }
+        }
+    }
+
+    let own_weight: HashMap<String, i64> = nodes
+        .keys()
+        .map(|id| (id.clone(), *participant_counts.get(id).unwrap_or(&1)))
+        .collect();
+
+    let mut memo: HashMap<String, i64> = HashMap::new();
+    for node_id in nodes.keys() {
+        ghost_subtree_weight(node_id, &child_map, &own_weight, &mut memo, &mut std::collections::HashSet::new());
+    }
+
+    let roots: Vec<&EpochNode> = nodes
+        .values()
+        .filter(|n| match &n.parent_id {
+            None => true,
+            Some(p) => !nodes.contains_key(p),
+        })
+        .collect();
+
+    let mut best_root = *roots.first()?;
+    for candidate in &roots[1..] {
+        let cw = *memo.get(&candidate.node_id).unwrap_or(&0);
+        let bw = *memo.get(&best_root.node_id).unwrap_or(&0);
+        if cw != bw {
+            if cw > bw {
+                best_root = candidate;
+            }
+        } else if ghost_tiebreak_wins(candidate, best_root) {
+            best_root = candidate;
+        }
+    }
+
+    let mut current = best_root.node_id.clone();
+    let mut seen = std::collections::HashSet::new();
+    loop {
+        if !seen.insert(current.clone()) {
+            break;
+        }
+        let kids = match child_map.get(&current) {
+            Some(k) if !k.is_empty() => k,
+            _ => break,
+        };
+        let mut best_kid = &kids[0];
+        for kid in &kids[1..] {
+            let kw = *memo.get(kid).unwrap_or(&0);
+            let bw = *memo.get(best_kid).unwrap_or(&0);
+            if kw != bw {
+                if kw > bw {
+                    best_kid = kid;
+                }
+            } else if ghost_tiebreak_wins(nodes.get(kid).unwrap(), nodes.get(best_kid).unwrap()) {
+                best_kid = kid;
+            }
+        }
+        current = best_kid.clone();
+    }
+
+    Some(current)
+}
+
 fn fault_delay_ms(faults: &[String]) -> i64 {
     for f in faults {
         if let Some(rest) = f.strip_prefix("delay_validation:") {
@@ -191,6 +414,17 @@ fn simulate(s: &Scenario) -> Envelope {
         other => other,
     });
 
+    let hash_algo = HashAlgorithm::from_name(s.hash_algorithm.as_deref().unwrap_or("sha256"));
+
+    let mut participants_by_node: HashMap<String, Vec<String>> = HashMap::new();
+    for ev in &s.event_stream {
+        if ev.event == "epoch_issue" {
+            if let (Some(node_id), Some(participants)) = (&ev.node_id, &ev.participants) {
+                participants_by_node.insert(node_id.clone(), participants.clone());
+            }
+        }
+    }
+
     let mut observed: HashMap<i32, Vec<(String, String)>> = HashMap::new();
     let mut children: HashMap<String, Vec<(i32, String, String)>> = HashMap::new();
     let mut detection = false;
@@ -199,6 +433,20 @@ fn simulate(s: &Scenario) -> Envelope {
     let mut errors = Vec::new();
     let mut messages_dropped: i32 = 0;
 
+    // Bounded anti-replay window (DTLS/IPsec-style): each `replay_attempt`
+    // burst's `count` is split against the window size — the portion that
+    // fits is a duplicate already covered by the window, the rest has aged
+    // past the low-water mark and is a hard drop.
+    let replay_window_size: i64 = if s.expectations.allow_replay_gap.max_messages > 0 {
+        s.expectations.allow_replay_gap.max_messages as i64
+    } else {
+        64
+    };
+    let mut replay_window_duplicates: i32 = 0;
+    let mut replay_window_underflows: i32 = 0;
+    let mut first_replay_t: Option<i64> = None;
+    let mut last_replay_t: Option<i64> = None;
+
     for ev in &events {
         if ev.event == "epoch_issue" {
             let node_id = ev.node_id.as_ref().expect("node_id required");
@@ -242,9 +490,36 @@ fn simulate(s: &Scenario) -> Envelope {
                 }
             }
 
+            if let Some(declared_digest) = &node.membership_digest {
+                if let Some(participants) = participants_by_node.get(&node.node_id) {
+                    if let Some(computed_digest) = merkle_root(hash_algo, participants) {
+                        if computed_digest != *declared_digest
+                            && !errors.contains(&"MEMBERSHIP_DIGEST_MISMATCH".to_string())
+                        {
+                            errors.push("MEMBERSHIP_DIGEST_MISMATCH".to_string());
+                        }
+                    }
+                }
+            }
+
+            if let (Some(prev), Some(digest)) = (&node.previous_epoch_hash, &node.membership_digest) {
+                let computed_eare_hash = expected_eare_hash(hash_algo, node.epoch_id, prev, digest);
+                if computed_eare_hash != node.eare_hash
+                    && !errors.contains(&"EARE_HASH_MISMATCH".to_string())
+                {
+                    errors.push("EARE_HASH_MISMATCH".to_string());
+                }
+            }
+
             if let (Some(prev), Some(parent_id)) = (&node.previous_epoch_hash, &node.parent_id) {
                 if let Some(parent) = nodes.get(parent_id) {
-                    if parent.eare_hash != *prev {
+                    let parent_expected_hash = match (&parent.previous_epoch_hash, &parent.membership_digest) {
+                        (Some(parent_prev), Some(parent_digest)) => {
+                            expected_eare_hash(hash_algo, parent.epoch_id, parent_prev, parent_digest)
+                        }
+                        _ => parent.eare_hash.clone(),
+                    };
+                    if parent_expected_hash != *prev {
                         if !errors.contains(&"HASH_CHAIN_BREAK".to_string()) {
                             errors.push("HASH_CHAIN_BREAK".to_string());
                         }
@@ -254,10 +529,29 @@ fn simulate(s: &Scenario) -> Envelope {
         } else if ev.event == "replay_attempt" {
             if let Some(c) = ev.count {
                 messages_dropped += c;
+
+                first_replay_t.get_or_insert(ev.t);
+                last_replay_t = Some(ev.t);
+
+                let burst = c as i64;
+                let in_window = burst.min(replay_window_size);
+                let underflow = burst - in_window;
+                replay_window_duplicates += in_window as i32;
+                if underflow > 0 {
+                    replay_window_underflows += underflow as i32;
+                    if !errors.contains(&"REPLAY_WINDOW_UNDERFLOW".to_string()) {
+                        errors.push("REPLAY_WINDOW_UNDERFLOW".to_string());
+                    }
+                }
             }
         }
     }
 
+    let replay_window_span_ms = match (first_replay_t, last_replay_t) {
+        (Some(first), Some(last)) => Some(std::cmp::max(0, last - first)),
+        _ => None,
+    };
+
     let mut all_entries: Vec<(String, String)> = observed.values().flatten().cloned().collect();
     all_entries.sort_by(|a, b| {
         let na = nodes.get(&a.0).unwrap();
@@ -287,6 +581,37 @@ fn simulate(s: &Scenario) -> Envelope {
         }
     }
 
+    if s.fork_choice.as_deref() == Some("ghost") {
+        let mut participant_counts: HashMap<String, i64> = HashMap::new();
+        for ev in &s.event_stream {
+            if ev.event == "epoch_issue" {
+                if let Some(node_id) = &ev.node_id {
+                    let count = ev.participants.as_ref().map(|p| p.len() as i64).unwrap_or(1);
+                    participant_counts.insert(node_id.clone(), count);
+                }
+            }
+        }
+        // Restrict GHOST to nodes that actually had an `epoch_issue` event,
+        // the same gate `all_entries` above applies to the default
+        // longest-chain winner — a node only declared in `s.graph.nodes`
+        // but never issued shouldn't be eligible to be crowned or walked
+        // through.
+        let observed_ids: std::collections::HashSet<&str> =
+            all_entries.iter().map(|(node_id, _)| node_id.as_str()).collect();
+        let observed_nodes: HashMap<String, EpochNode> = nodes
+            .iter()
+            .filter(|(node_id, _)| observed_ids.contains(node_id.as_str()))
+            .map(|(node_id, n)| (node_id.clone(), n.clone()))
+            .collect();
+        if let Some(winner) = ghost_winner(&observed_nodes, &participant_counts) {
+            if let Some(n) = nodes.get(&winner) {
+                winning_node_id = Some(n.node_id.clone());
+                winning_hash = Some(n.eare_hash.clone());
+                winning_epoch_id = Some(n.epoch_id);
+            }
+        }
+    }
+
     let detection_reference = if s.expectations.detection_reference == "fork_observable" {
         detection_time
     } else {
@@ -320,6 +645,9 @@ fn simulate(s: &Scenario) -> Envelope {
         winning_hash,
         winning_node_id,
         messages_dropped,
+        replay_window_span_ms,
+        replay_window_duplicates,
+        replay_window_underflows,
         healing_actions: Vec::new(),
         errors,
         false_positives: false_pos,
@@ -377,6 +705,14 @@ fn evaluate(s: &Scenario, env: &mut Envelope) {
         failures.push("replay_gap_messages".to_string());
     }
 
+    if exp.allow_replay_gap.max_ms > 0 {
+        if let Some(span) = env.replay_window_span_ms {
+            if span > exp.allow_replay_gap.max_ms as i64 {
+                failures.push("replay_gap_window".to_string());
+            }
+        }
+    }
+
     let missing_errors: Vec<&String> = exp
         .expected_error_categories
         .iter()
@@ -392,9 +728,10 @@ fn evaluate(s: &Scenario, env: &mut Envelope) {
     }
 }
 
-fn parse_args() -> (String, Option<String>) {
+fn parse_args() -> (String, Option<String>, Option<String>) {
     let mut corpus = "tests/common/adversarial/epoch_forks.json".to_string();
     let mut scenario: Option<String> = None;
+    let mut emit_dot: Option<String> = None;
     let args: Vec<String> = env::args().collect();
     let mut i = 1;
     while i < args.len() {
@@ -411,11 +748,114 @@ fn parse_args() -> (String, Option<String>) {
                     i += 1;
                 }
             }
+            "--emit-dot" => {
+                if i + 1 < args.len() {
+                    emit_dot = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
             _ => {}
         }
         i += 1;
     }
-    (corpus, scenario)
+    (corpus, scenario, emit_dot)
+}
+
+/// Renders `s`'s epoch graph as a Graphviz `digraph`: one node per
+/// `EpochNode` (labeled with its `epoch_id`, an `eare_hash` prefix, and
+/// `node_id`), one edge per `parent_id` link. An edge is colored red if
+/// `graph.edges` records it as `"fork"` typed or if either endpoint shares an
+/// `epoch_id` with another node under a different `eare_hash`; the winning
+/// head (`env.winning_node_id`) is drawn bold and filled; nodes whose
+/// `previous_epoch_hash` doesn't match their parent's `eare_hash` get a
+/// dashed red outline to flag the `HASH_CHAIN_BREAK`.
+fn render_dot(s: &Scenario, env: &Envelope) -> String {
+    let nodes: HashMap<&str, &EpochNode> = s
+        .graph
+        .nodes
+        .iter()
+        .map(|n| (n.node_id.as_str(), n))
+        .collect();
+
+    let mut edge_types: HashMap<(&str, &str), &str> = HashMap::new();
+    for e in &s.graph.edges {
+        edge_types.insert((e.from.as_str(), e.to.as_str()), e.edge_type.as_str());
+        edge_types.insert((e.to.as_str(), e.from.as_str()), e.edge_type.as_str());
+    }
+
+    let mut hashes_by_epoch: HashMap<i32, std::collections::HashSet<&str>> = HashMap::new();
+    for n in &s.graph.nodes {
+        hashes_by_epoch
+            .entry(n.epoch_id)
+            .or_default()
+            .insert(n.eare_hash.as_str());
+    }
+    let is_divergent = |n: &EpochNode| {
+        hashes_by_epoch
+            .get(&n.epoch_id)
+            .map(|hs| hs.len() > 1)
+            .unwrap_or(false)
+    };
+
+    let is_chain_break = |n: &EpochNode| {
+        if let (Some(prev), Some(parent_id)) = (&n.previous_epoch_hash, &n.parent_id) {
+            if let Some(parent) = nodes.get(parent_id.as_str()) {
+                return parent.eare_hash != *prev;
+            }
+        }
+        false
+    };
+
+    let mut out = String::new();
+    out.push_str("digraph epoch_fork {\n");
+    out.push_str("  rankdir=TB;\n");
+    out.push_str("  node [shape=box, fontname=\"monospace\"];\n");
+
+    for n in &s.graph.nodes {
+        let hash_prefix = &n.eare_hash[..n.eare_hash.len().min(8)];
+        let mut style_attrs = Vec::new();
+        if env.winning_node_id.as_deref() == Some(n.node_id.as_str()) {
+            style_attrs.push("style=\"bold,filled\"".to_string());
+            style_attrs.push("fillcolor=lightgreen".to_string());
+        }
+        if is_chain_break(n) {
+            style_attrs.push("color=red".to_string());
+            style_attrs.push("style=\"dashed\"".to_string());
+            style_attrs.push("peripheries=2".to_string());
+        }
+        let attrs = if style_attrs.is_empty() {
+            String::new()
+        } else {
+            format!(", {}", style_attrs.join(", "))
+        };
+        out.push_str(&format!(
+            "  \"{}\" [label=\"epoch {}\\n{}\\n{}\"{}];\n",
+            n.node_id, n.epoch_id, hash_prefix, n.node_id, attrs
+        ));
+    }
+
+    for n in &s.graph.nodes {
+        if let Some(parent_id) = &n.parent_id {
+            if !nodes.contains_key(parent_id.as_str()) {
+                continue;
+            }
+            let edge_type = edge_types
+                .get(&(n.node_id.as_str(), parent_id.as_str()))
+                .copied()
+                .unwrap_or("linear");
+            let red = edge_type == "fork"
+                || is_divergent(n)
+                || nodes.get(parent_id.as_str()).is_some_and(|p| is_divergent(*p));
+            let color_attr = if red { " [color=red]" } else { "" };
+            out.push_str(&format!(
+                "  \"{}\" -> \"{}\"{};\n",
+                n.node_id, parent_id, color_attr
+            ));
+        }
+    }
+
+    out.push_str("}\n");
+    out
 }
 
 fn load_corpus(path: &str) -> Vec<Scenario> {
@@ -429,7 +869,7 @@ fn load_corpus(path: &str) -> Vec<Scenario> {
 }
 
 fn main() {
-    let (corpus_path, scenario_id) = parse_args();
+    let (corpus_path, scenario_id, emit_dot) = parse_args();
     let scenarios = load_corpus(&corpus_path);
     let selected: Vec<Scenario> = if let Some(id) = scenario_id {
         scenarios
@@ -452,5 +892,11 @@ fn main() {
         let mut env = simulate(&scenario);
         evaluate(&scenario, &mut env);
         println!("{}", serde_json::to_string(&env).unwrap());
+
+        if let Some(path) = &emit_dot {
+            let dot = render_dot(&scenario, &env);
+            fs::write(path, dot).expect("Failed to write --emit-dot output");
+            eprintln!("📄 DOT graph written to {}", path);
+        }
     }
 }
@@ -1,96 +1,173 @@
 use base64::{engine::general_purpose, Engine as _};
 use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
 use serde::Deserialize;
-use serde::ser::{SerializeMap, SerializeSeq};
-use serde_cbor::value::{to_value, Value};
 use sha2::{Digest, Sha256};
 use std::error::Error;
 use std::fs;
 
-fn encode_canonical(value: &serde_json::Value) -> Result<Vec<u8>, serde_cbor::Error> {
-    let val: Value = to_value(value)?;
-    serde_cbor::to_vec(&CanonValue(&val))
+type HmacSha256 = Hmac<Sha256>;
+
+/// Mirrors `tools/generators/generate_e2e_test_vectors.rs`'s `FramingMode`:
+/// either the step's `message` is on the wire as-is, or `wire_frame` must be
+/// stripped first before the inner message can be validated.
+#[derive(Deserialize, Clone)]
+#[serde(tag = "mode")]
+enum FramingMode {
+    Plain,
+    Obfuscated { node_key: String, pad_dist: PadDistribution },
 }
 
-struct CanonValue<'a>(&'a Value);
-
-impl<'a> serde::Serialize for CanonValue<'a> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        match self.0 {
-            Value::Array(items) => {
-                let mut seq = serializer.serialize_seq(Some(items.len()))?;
-                for item in items {
-                    seq.serialize_element(&CanonValue(item))?;
-                }
-                seq.end()
-            }
-            Value::Map(map) => {
-                let mut entries = Vec::with_capacity(map.len());
-                for (k, v) in map.iter() {
-                    let key_bytes = serde_cbor::to_vec(&CanonValue(k)).map_err(serde::ser::Error::custom)?;
-                    entries.push((key_bytes, k, v));
-                }
-                entries.sort_by(|(kb1, _, _), (kb2, _, _)| kb1.len().cmp(&kb2.len()).then_with(|| kb1.cmp(kb2)));
-
-                let mut map_ser = serializer.serialize_map(Some(entries.len()))?;
-                for (_, k, v) in entries {
-                    map_ser.serialize_entry(&CanonValue(k), &CanonValue(v))?;
-                }
-                map_ser.end()
-            }
-            other => other.serialize(serializer),
-        }
+#[derive(Deserialize, Clone)]
+struct PadDistribution {
+    #[allow(dead_code)]
+    min_bytes: usize,
+    #[allow(dead_code)]
+    max_bytes: usize,
+}
+
+/// The post-handshake HKDF-Expand key schedule, keyed by the
+/// `handshake_hash`-derived PRK.
+struct KeySchedule {
+    session_id: [u8; 32],
+}
+
+/// Derives the key schedule from `handshake_hash`, the transcript hash of
+/// HANDSHAKE_INIT || HANDSHAKE_RESPONSE.
+fn derive_key_schedule(handshake_hash: &[u8]) -> Result<KeySchedule, Box<dyn Error>> {
+    let hk = Hkdf::<Sha256>::new(None, handshake_hash);
+
+    let mut session_id = [0u8; 32];
+    hk.expand(b"FoxWhisper-SessionId", &mut session_id)
+        .map_err(|e| format!("hkdf expand failed: {e}"))?;
+
+    Ok(KeySchedule { session_id })
+}
+
+/// Inverse of `obfuscate_frame` in the generator: verifies the trailing MAC,
+/// decrypts the length prefix, then strips the leading padding to recover
+/// the original payload bytes.
+fn strip_framing(node_key: &str, frame: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    if frame.len() < 4 + 16 {
+        return Err("obfuscated frame too short".into());
+    }
+    let node_key_bytes = hex::decode(node_key)?;
+    let hk = Hkdf::<Sha256>::new(None, &node_key_bytes);
+
+    let (frame_body, tag) = frame.split_at(frame.len() - 16);
+    let mut mac_key = [0u8; 32];
+    hk.expand(b"FoxWhisper-ObfsMac", &mut mac_key)
+        .map_err(|e| format!("hkdf expand failed: {e}"))?;
+    let mut mac = HmacSha256::new_from_slice(&mac_key)?;
+    mac.update(frame_body);
+    // `tag` is only the leading 16 bytes of the full HMAC-SHA256 output (see
+    // `obfuscate_frame` in the generator), so this must compare against
+    // that truncated prefix rather than the full tag `verify_slice` expects.
+    mac.verify_truncated_left(tag)
+        .map_err(|_| "obfuscated frame MAC mismatch".to_string())?;
+
+    let mut len_keystream = [0u8; 4];
+    hk.expand(b"FoxWhisper-ObfsLen", &mut len_keystream)
+        .map_err(|e| format!("hkdf expand failed: {e}"))?;
+    let mut len_bytes = [0u8; 4];
+    len_bytes.copy_from_slice(&frame_body[0..4]);
+    for (b, k) in len_bytes.iter_mut().zip(len_keystream.iter()) {
+        *b ^= k;
+    }
+    let payload_len = u32::from_le_bytes(len_bytes) as usize;
+
+    let rest = &frame_body[4..];
+    if payload_len > rest.len() {
+        return Err("obfuscated frame declares a payload longer than the frame".into());
     }
+    let padding_len = rest.len() - payload_len;
+    Ok(rest[padding_len..].to_vec())
 }
 
 #[derive(Deserialize)]
 struct FlowDoc {
     handshake_flow: HandshakeFlow,
+    /// Present alongside `handshake_flow` in the generator's output; the
+    /// only vector that actually exercises `FramingMode::Obfuscated` (and
+    /// therefore `strip_framing`), so it must be validated too, not just
+    /// deserialized and ignored.
+    #[serde(default)]
+    handshake_flow_obfuscated: Option<HandshakeFlow>,
 }
 
 #[derive(Deserialize)]
 struct HandshakeFlow {
     steps: Vec<Step>,
+    #[serde(default)]
+    framing_mode: Option<FramingMode>,
 }
 
 #[derive(Deserialize)]
 struct Step {
     message: serde_json::Value,
+    #[serde(default)]
+    wire_frame: Option<String>,
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let data = fs::read_to_string("tests/common/handshake/end_to_end_test_vectors.json")?;
-    let doc: FlowDoc = serde_json::from_str(&data)?;
-    let steps = &doc.handshake_flow.steps;
+/// The message this step actually carried on the wire: `wire_frame` stripped
+/// of its obfuscation framing if present, else `message` as-is.
+fn inner_message(step: &Step, framing: &FramingMode) -> Result<serde_json::Value, Box<dyn Error>> {
+    match (&step.wire_frame, framing) {
+        (Some(wire_frame), FramingMode::Obfuscated { node_key, .. }) => {
+            let frame = general_purpose::STANDARD.decode(wire_frame)?;
+            let payload = strip_framing(node_key, &frame)?;
+            Ok(serde_json::from_slice(&payload)?)
+        }
+        _ => Ok(step.message.clone()),
+    }
+}
+
+fn validate_flow(flow: &HandshakeFlow, label: &str) -> Result<(), Box<dyn Error>> {
+    let steps = &flow.steps;
     if steps.len() < 3 {
-        return Err("handshake_flow missing steps".into());
+        return Err(format!("{} missing steps", label).into());
     }
-    let resp = &steps[1].message;
-    let complete = &steps[2].message;
+    let framing = flow.framing_mode.clone().unwrap_or(FramingMode::Plain);
+    let init = inner_message(&steps[0], &framing)?;
+    let resp = inner_message(&steps[1], &framing)?;
+    let complete = inner_message(&steps[2], &framing)?;
 
-    let encoded = encode_canonical(resp)?;
-    let hash = Sha256::digest(&encoded);
+    // Mirrors the generator's `transcript_bytes`/`handshake_hash_bytes`:
+    // SHA-256(HANDSHAKE_INIT_bytes || HANDSHAKE_RESPONSE_bytes), not a
+    // CBOR-canonicalized encoding of the response alone.
+    let mut transcript = serde_json::to_vec(&init)?;
+    transcript.extend(serde_json::to_vec(&resp)?);
+    let hash = Sha256::digest(&transcript);
     let handshake_hash = general_purpose::STANDARD.encode(hash);
 
-    let hk = Hkdf::<Sha256>::new(None, &hash);
-    let mut okm = [0u8; 32];
-    hk.expand(b"FoxWhisper-SessionId", &mut okm)
-        .map_err(|e| format!("hkdf expand failed: {e}"))?;
-    let session_id = general_purpose::STANDARD.encode(okm);
+    let schedule = derive_key_schedule(&hash)?;
+    let session_id = general_purpose::STANDARD.encode(schedule.session_id);
 
     let expected_hash = complete["handshake_hash"].as_str().unwrap_or("");
     let expected_session = complete["session_id"].as_str().unwrap_or("");
 
     if handshake_hash != expected_hash {
-        return Err(format!("handshake_hash mismatch: {} != {}", expected_hash, handshake_hash).into());
+        return Err(format!("{}: handshake_hash mismatch: {} != {}", label, expected_hash, handshake_hash).into());
     }
     if session_id != expected_session {
-        return Err(format!("session_id mismatch: {} != {}", expected_session, session_id).into());
+        return Err(format!("{}: session_id mismatch: {} != {}", label, expected_session, session_id).into());
+    }
+
+    println!("✅ {} derivation matches (Rust)", label);
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let data = fs::read_to_string("tests/common/handshake/end_to_end_test_vectors.json")?;
+    let doc: FlowDoc = serde_json::from_str(&data)?;
+
+    validate_flow(&doc.handshake_flow, "handshake_flow")?;
+
+    // The only vector where `FramingMode::Obfuscated` is actually present,
+    // so this is what exercises `strip_framing`'s HMAC/length/padding logic.
+    if let Some(obfuscated) = &doc.handshake_flow_obfuscated {
+        validate_flow(obfuscated, "handshake_flow_obfuscated")?;
     }
 
-    println!("✅ handshake_flow derivation matches (Rust)");
     Ok(())
 }
@@ -32,7 +32,153 @@ struct Corpus {
     seeds: Vec<Seed>,
 }
 
+/// A Wycheproof-layout corpus: a named algorithm/schema plus test groups,
+/// each carrying shared parameters and a list of individual test cases.
+#[derive(Debug, Deserialize)]
+struct WycheproofFile {
+    #[allow(dead_code)]
+    algorithm: String,
+    #[serde(default)]
+    #[allow(dead_code)]
+    schema: Option<String>,
+    #[serde(rename = "testGroups")]
+    test_groups: Vec<WycheproofGroup>,
+}
+
+/// One group's `type` doubles as the `message_type` passed to
+/// `validate_handshake`; `params` are fields shared by every test in the
+/// group (e.g. a common public key), overridden per-case by that case's own
+/// `fields`.
+#[derive(Debug, Deserialize)]
+struct WycheproofGroup {
+    #[serde(rename = "type")]
+    group_type: String,
+    #[serde(flatten)]
+    params: Map<String, Value>,
+    #[serde(default)]
+    tests: Vec<WycheproofTestCase>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WycheproofTestCase {
+    #[serde(rename = "tcId")]
+    tc_id: i64,
+    #[serde(default)]
+    comment: String,
+    #[serde(default)]
+    flags: Vec<String>,
+    result: String,
+    #[serde(flatten)]
+    fields: Map<String, Value>,
+}
+
+/// Outcome of running one `WycheproofTestCase` through `validate_handshake`.
+#[derive(Debug, serde::Serialize)]
+struct WycheproofCaseResult {
+    tc_id: i64,
+    message_type: String,
+    result: String,
+    passed: bool,
+    flags: Vec<String>,
+    comment: String,
+}
+
+/// Runs every case in every group through `validate_handshake`, merging each
+/// group's shared `params` under the case's own `fields` (case fields win on
+/// conflict). `"valid"` cases must validate, `"invalid"` must not, and
+/// `"acceptable"` passes regardless — weak-but-tolerated vectors shouldn't
+/// fail the run. `flags_only`/`flags_exclude` filter which cases run at all.
+fn run_wycheproof(
+    file: &WycheproofFile,
+    flags_only: Option<&[String]>,
+    flags_exclude: Option<&[String]>,
+) -> Vec<WycheproofCaseResult> {
+    let mut results = Vec::new();
+
+    for group in &file.test_groups {
+        for test in &group.tests {
+            if let Some(only) = flags_only {
+                if !test.flags.iter().any(|f| only.contains(f)) {
+                    continue;
+                }
+            }
+            if let Some(exclude) = flags_exclude {
+                if test.flags.iter().any(|f| exclude.contains(f)) {
+                    continue;
+                }
+            }
+
+            let mut fields = group.params.clone();
+            for (key, value) in &test.fields {
+                fields.insert(key.clone(), value.clone());
+            }
+
+            let observed_valid = validate_handshake(&group.group_type, &fields);
+            let passed = match test.result.as_str() {
+                "valid" => observed_valid,
+                "invalid" => !observed_valid,
+                "acceptable" => true,
+                _ => false,
+            };
+
+            results.push(WycheproofCaseResult {
+                tc_id: test.tc_id,
+                message_type: group.group_type.clone(),
+                result: test.result.clone(),
+                passed,
+                flags: test.flags.clone(),
+                comment: test.comment.clone(),
+            });
+        }
+    }
+
+    results
+}
+
+struct CliArgs {
+    wycheproof_path: Option<String>,
+    flags_only: Option<Vec<String>>,
+    flags_exclude: Option<Vec<String>>,
+}
+
+fn parse_args() -> CliArgs {
+    let mut wycheproof_path = None;
+    let mut flags_only = None;
+    let mut flags_exclude = None;
+
+    let args: Vec<String> = std::env::args().collect();
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--wycheproof" => {
+                if i + 1 < args.len() {
+                    wycheproof_path = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--flags-only" => {
+                if i + 1 < args.len() {
+                    flags_only = Some(args[i + 1].split(',').map(|s| s.to_string()).collect());
+                    i += 1;
+                }
+            }
+            "--flags-exclude" => {
+                if i + 1 < args.len() {
+                    flags_exclude = Some(args[i + 1].split(',').map(|s| s.to_string()).collect());
+                    i += 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    CliArgs { wycheproof_path, flags_only, flags_exclude }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let cli = parse_args();
+
     let corpus_path = util::root_path("tests/common/adversarial/malformed_packets.json");
     let content = fs::read_to_string(corpus_path)?;
     let corpus: Corpus = serde_json::from_str(&content)?;
@@ -85,6 +231,36 @@ fn main() -> Result<(), Box<dyn Error>> {
         results_summary.len()
     );
 
+    let mut wycheproof_results: Vec<WycheproofCaseResult> = Vec::new();
+    if let Some(path) = &cli.wycheproof_path {
+        let content = fs::read_to_string(path)?;
+        let file: WycheproofFile = serde_json::from_str(&content)?;
+        wycheproof_results = run_wycheproof(
+            &file,
+            cli.flags_only.as_deref(),
+            cli.flags_exclude.as_deref(),
+        );
+
+        let wycheproof_passed = wycheproof_results.iter().filter(|r| r.passed).count();
+        for case in &wycheproof_results {
+            if case.passed {
+                println!("✅ wycheproof::{} ({})", case.tc_id, case.message_type);
+            } else {
+                println!(
+                    "❌ wycheproof::{} ({}, result={})",
+                    case.tc_id, case.message_type, case.result
+                );
+            }
+        }
+        println!(
+            "Wycheproof: {}/{} cases matched expectations",
+            wycheproof_passed,
+            wycheproof_results.len()
+        );
+    }
+
+    let all_wycheproof_passed = wycheproof_results.iter().all(|r| r.passed);
+
     let payload = serde_json::json!({
         "language": "rust",
         "test": "malformed_fuzz",
@@ -92,12 +268,13 @@ fn main() -> Result<(), Box<dyn Error>> {
         "passed": passed,
         "failed": results_summary.len() - passed,
         "results": results_summary,
+        "wycheproof": wycheproof_results,
     });
 
     util::write_json("rust_malformed_packet_fuzz_results.json", &payload)?;
     println!("📄 Results saved to results/rust_malformed_packet_fuzz_results.json");
 
-    if passed != results_summary.len() {
+    if passed != results_summary.len() || !all_wycheproof_passed {
         std::process::exit(1);
     }
 
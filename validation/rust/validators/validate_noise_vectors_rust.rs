@@ -0,0 +1,254 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+
+mod util;
+use util::{load_json, write_json};
+
+/// A single Noise-Framework-style test vector: the protocol name, the
+/// initiator/responder static and ephemeral keys as hex, and the ordered
+/// handshake messages each side exchanged.
+#[derive(Debug, Deserialize, Clone)]
+struct NoiseVector {
+    name: String,
+    protocol_name: String,
+    init_static: String,
+    init_ephemeral: String,
+    resp_static: String,
+    resp_ephemeral: String,
+    messages: Vec<NoiseMessage>,
+    #[serde(default)]
+    handshake_hash: Option<String>,
+}
+
+/// One step of the handshake: the plaintext `payload` fed into the state
+/// machine and the hex `ciphertext` it is expected to produce.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct NoiseMessage {
+    payload: String,
+    ciphertext: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NoiseVectorFile {
+    vectors: Vec<NoiseVector>,
+}
+
+#[derive(Debug, Serialize)]
+struct StepResult {
+    step: usize,
+    matched: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actual: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    byte_diff: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct VectorResult {
+    name: String,
+    mode: String,
+    valid: bool,
+    steps: Vec<StepResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    handshake_hash_matched: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+struct Summary {
+    corpus: String,
+    total: usize,
+    passed: usize,
+    failed: usize,
+    results: Vec<VectorResult>,
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    if s.len() % 2 != 0 {
+        return Err(format!("odd-length hex string: {s}").into());
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|e| e.into()))
+        .collect()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// First differing byte offset and the two byte values there, for a
+/// human-readable mismatch report.
+fn byte_diff(expected: &[u8], actual: &[u8]) -> String {
+    if expected.len() != actual.len() {
+        return format!(
+            "length mismatch: expected {} bytes, got {} bytes",
+            expected.len(),
+            actual.len()
+        );
+    }
+    for (i, (e, a)) in expected.iter().zip(actual.iter()).enumerate() {
+        if e != a {
+            return format!("first diff at byte {i}: expected 0x{:02x}, got 0x{:02x}", e, a);
+        }
+    }
+    "no differing bytes".to_string()
+}
+
+/// Minimal stand-in handshake state machine: each message's "ciphertext" is
+/// `SHA-256(running_transcript || payload)`, and the transcript accumulates
+/// every payload seen so far. This mirrors the chaining structure of the
+/// real FoxWhisper key schedule (`tools/generators/generate_e2e_test_vectors.rs`)
+/// closely enough to catch transcript-ordering and payload-framing bugs in a
+/// decoder without requiring a full Noise `CipherState`.
+struct HandshakeState {
+    transcript: Vec<u8>,
+}
+
+impl HandshakeState {
+    fn new(vector: &NoiseVector) -> Result<Self, Box<dyn Error>> {
+        let mut transcript = Vec::new();
+        transcript.extend_from_slice(vector.protocol_name.as_bytes());
+        transcript.extend_from_slice(&decode_hex(&vector.init_static)?);
+        transcript.extend_from_slice(&decode_hex(&vector.init_ephemeral)?);
+        transcript.extend_from_slice(&decode_hex(&vector.resp_static)?);
+        transcript.extend_from_slice(&decode_hex(&vector.resp_ephemeral)?);
+        Ok(Self { transcript })
+    }
+
+    fn step(&mut self, payload: &[u8]) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.transcript);
+        hasher.update(payload);
+        let digest = hasher.finalize().to_vec();
+        self.transcript.extend_from_slice(payload);
+        digest
+    }
+
+    fn handshake_hash(&self) -> Vec<u8> {
+        Sha256::digest(&self.transcript).to_vec()
+    }
+}
+
+/// Walks `vector.messages` through `HandshakeState`, asserting each produced
+/// ciphertext matches the stored hex. Returns one `StepResult` per message,
+/// plus the handshake-hash comparison if the vector carries one.
+fn verify_vector(vector: &NoiseVector, mode: &str) -> Result<VectorResult, Box<dyn Error>> {
+    let mut state = HandshakeState::new(vector)?;
+    let mut steps = Vec::with_capacity(vector.messages.len());
+    let mut all_matched = true;
+
+    for (idx, message) in vector.messages.iter().enumerate() {
+        let payload = decode_hex(&message.payload)?;
+        let expected = decode_hex(&message.ciphertext)?;
+        let actual = state.step(&payload);
+        let matched = actual == expected;
+        all_matched &= matched;
+
+        steps.push(StepResult {
+            step: idx,
+            matched,
+            expected: if matched { None } else { Some(message.ciphertext.clone()) },
+            actual: if matched { None } else { Some(encode_hex(&actual)) },
+            byte_diff: if matched { None } else { Some(byte_diff(&expected, &actual)) },
+        });
+    }
+
+    let handshake_hash_matched = match &vector.handshake_hash {
+        Some(expected_hex) => {
+            let expected = decode_hex(expected_hex)?;
+            let matched = state.handshake_hash() == expected;
+            all_matched &= matched;
+            Some(matched)
+        }
+        None => None,
+    };
+
+    Ok(VectorResult {
+        name: vector.name.clone(),
+        mode: mode.to_string(),
+        valid: all_matched,
+        steps,
+        handshake_hash_matched,
+    })
+}
+
+/// Round-trip mode: builds a vector from scratch (rather than loading one
+/// from disk) and immediately verifies it with the same `verify_vector` path
+/// used for known-answer vectors, proving the generator and the verifier
+/// agree on the transcript chaining rules.
+fn round_trip_vector() -> Result<VectorResult, Box<dyn Error>> {
+    let mut vector = NoiseVector {
+        name: "round_trip_self_check".to_string(),
+        protocol_name: "FoxWhisper_Hybrid_25519+Kyber1024_ChaChaPoly_SHA256".to_string(),
+        init_static: "11".repeat(32),
+        init_ephemeral: "22".repeat(32),
+        resp_static: "33".repeat(32),
+        resp_ephemeral: "44".repeat(32),
+        messages: vec![
+            NoiseMessage {
+                payload: "deadbeef".to_string(),
+                ciphertext: String::new(),
+            },
+            NoiseMessage {
+                payload: "cafef00d".to_string(),
+                ciphertext: String::new(),
+            },
+        ],
+        handshake_hash: None,
+    };
+
+    let mut state = HandshakeState::new(&vector)?;
+    for message in vector.messages.iter_mut() {
+        let payload = decode_hex(&message.payload)?;
+        message.ciphertext = encode_hex(&state.step(&payload));
+    }
+    vector.handshake_hash = Some(encode_hex(&state.handshake_hash()));
+
+    verify_vector(&vector, "round_trip")
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let corpus_path = "tests/common/handshake/noise_vectors.json";
+    let file: NoiseVectorFile = load_json(corpus_path)?;
+
+    let mut results = Vec::new();
+    for vector in file.vectors.iter() {
+        results.push(verify_vector(vector, "known_answer")?);
+    }
+    results.push(round_trip_vector()?);
+
+    let passed = results.iter().filter(|r| r.valid).count();
+    let failed = results.len() - passed;
+
+    let summary = Summary {
+        corpus: corpus_path.to_string(),
+        total: results.len(),
+        passed,
+        failed,
+        results,
+    };
+
+    write_json("rust_noise_vectors_summary.json", &summary)?;
+
+    if summary.failed > 0 {
+        for result in summary.results.iter().filter(|r| !r.valid) {
+            for step in result.steps.iter().filter(|s| !s.matched) {
+                eprintln!(
+                    "❌ {} [{}] step {}: {}",
+                    result.name,
+                    result.mode,
+                    step.step,
+                    step.byte_diff.as_deref().unwrap_or("mismatch")
+                );
+            }
+        }
+        eprintln!("❌ {} vector(s) failed", summary.failed);
+        std::process::exit(1);
+    }
+
+    println!("✅ All Noise-style handshake vectors verified (Rust)");
+    Ok(())
+}
@@ -1,16 +1,45 @@
+use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
 
 mod util;
 use util::{load_json, write_json};
 
+type HmacSha256 = Hmac<Sha256>;
+
+/// RFC 8188-style per-track content-encoding key schedule: the base nonce is
+/// derived once per `(track_id, key_epoch)`, and each record's real nonce is
+/// `base_nonce XOR record_seq`. Tracking the highest epoch seen and every
+/// `(key_epoch, record_seq)` pair used lets us detect both key-rotation
+/// violations and AEAD nonce reuse.
+#[derive(Debug, Default)]
+struct TrackKeyState {
+    highest_epoch: i64,
+    seen_records: HashSet<(i64, i64)>,
+}
+
+/// Deterministic stand-in for the salt-derived base nonce: `SHA-256(track_id
+/// || key_epoch)` truncated to 8 bytes, interpreted as little-endian.
+fn derive_base_nonce(track_id: &str, key_epoch: i64) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(track_id.as_bytes());
+    hasher.update(key_epoch.to_le_bytes());
+    let digest = hasher.finalize();
+    u64::from_le_bytes(digest[0..8].try_into().expect("sha256 digest is >= 8 bytes"))
+}
+
 #[derive(Debug, Deserialize)]
 struct SFUContext {
     sfu_id: String,
     room_id: String,
     expected_participants: Vec<String>,
     auth_mode: String,
+    /// Pre-shared room network key `K` (hex), used only when `auth_mode` is
+    /// `"secret_handshake"`.
+    #[serde(default)]
+    room_network_key: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -29,6 +58,10 @@ struct Participant {
     authz_tokens: Vec<String>,
     #[serde(default)]
     tracks: Vec<Track>,
+    /// Long-term identity key (hex) this participant is expected to prove
+    /// possession of during `hs_auth`, in `secret_handshake` mode.
+    #[serde(default)]
+    long_term_key: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -47,6 +80,24 @@ struct Event {
     requested_layers: Option<Vec<String>>,
     #[serde(default)]
     reported_bitrate: Option<i32>,
+    #[serde(default)]
+    key_epoch: Option<i64>,
+    #[serde(default)]
+    record_seq: Option<i64>,
+    /// Ephemeral DH public key (hex) offered in `hs_hello`.
+    #[serde(default)]
+    eph_pub: Option<String>,
+    /// HMAC (hex) the sender actually attached to `hs_hello`'s `eph_pub`,
+    /// computed with whatever network key they believe in.
+    #[serde(default)]
+    mac: Option<String>,
+    /// Network key (hex) the sender used to compute `mac`. A forwarding
+    /// attacker without `K` must guess or omit this.
+    #[serde(default)]
+    network_key: Option<String>,
+    /// Long-term identity key (hex) claimed in `hs_auth`.
+    #[serde(default)]
+    long_term_key: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -62,6 +113,8 @@ struct Expectations {
     max_extra_latency_ms: i32,
     max_false_positive_blocks: i32,
     max_false_negative_leaks: i32,
+    #[serde(default)]
+    max_nonce_reuse: i32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -103,6 +156,17 @@ struct SimulationResult {
     notes: Vec<String>,
 }
 
+/// Computes `HMAC-SHA256(hex_decode(key_hex), hex_decode(message_hex))` as a
+/// lowercase hex string. Returns `None` if either input isn't valid hex, in
+/// which case the caller should treat the HMAC as unverifiable (fails closed).
+fn hmac_hex(key_hex: &str, message_hex: &str) -> Option<String> {
+    let key = hex::decode(key_hex).ok()?;
+    let message = hex::decode(message_hex).ok()?;
+    let mut mac = HmacSha256::new_from_slice(&key).ok()?;
+    mac.update(&message);
+    Some(mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
 fn push_err(errors: &mut Vec<String>, code: &str) {
     if !errors.iter().any(|e| e == code) {
         errors.push(code.to_string());
@@ -127,6 +191,11 @@ fn simulate(s: &Scenario) -> SimulationResult {
     let mut bitrate_abuse_events = 0;
     let false_positive_blocks = 0;
     let false_negative_leaks = 0;
+    let mut nonce_reuse_events = 0;
+    let mut track_keys: HashMap<String, TrackKeyState> = HashMap::new();
+    // Participants who have presented a valid `hs_hello` HMAC and are now
+    // awaiting the identity-binding `hs_auth` step.
+    let mut hello_verified: HashSet<String> = HashSet::new();
 
     let mut detection_time: Option<i32> = None;
 
@@ -136,22 +205,63 @@ fn simulate(s: &Scenario) -> SimulationResult {
     for ev in events.iter() {
         match ev.event.as_str() {
             "join" => {
-                if let Some(pid) = &ev.participant {
-                    if let Some(part) = s.participants.iter().find(|p| &p.id == pid) {
-                        if let Some(tok) = &ev.token {
-                            if part.authz_tokens.contains(tok) {
-                                authed.insert(pid.clone());
+                // In secret_handshake mode, possessing a token proves
+                // nothing about a forwarding attacker; authentication only
+                // completes via hs_hello + hs_auth below.
+                if s.sfu_context.auth_mode != "secret_handshake" {
+                    if let Some(pid) = &ev.participant {
+                        if let Some(part) = s.participants.iter().find(|p| &p.id == pid) {
+                            if let Some(tok) = &ev.token {
+                                if part.authz_tokens.contains(tok) {
+                                    authed.insert(pid.clone());
+                                } else {
+                                    push_err(&mut errors, "IMPERSONATION");
+                                }
                             } else {
                                 push_err(&mut errors, "IMPERSONATION");
                             }
                         } else {
                             push_err(&mut errors, "IMPERSONATION");
                         }
+                    }
+                }
+            }
+            "hs_hello" => {
+                if let (Some(pid), Some(eph_pub_hex)) = (&ev.participant, &ev.eph_pub) {
+                    let room_key_hex = s.sfu_context.room_network_key.clone().unwrap_or_default();
+                    let expected_mac = hmac_hex(&room_key_hex, eph_pub_hex);
+                    let provided_mac = ev.mac.clone().unwrap_or_default();
+
+                    if expected_mac.is_some() && Some(provided_mac) == expected_mac {
+                        hello_verified.insert(pid.clone());
                     } else {
                         push_err(&mut errors, "IMPERSONATION");
                     }
                 }
             }
+            "hs_auth" => {
+                if let Some(pid) = &ev.participant {
+                    if !hello_verified.contains(pid) {
+                        push_err(&mut errors, "IMPERSONATION");
+                    } else {
+                        let claimed = ev.long_term_key.clone().unwrap_or_default();
+                        let expected = s
+                            .participants
+                            .iter()
+                            .find(|p| &p.id == pid)
+                            .and_then(|p| p.long_term_key.clone());
+
+                        match expected {
+                            Some(expected_key) if expected_key == claimed => {
+                                authed.insert(pid.clone());
+                            }
+                            _ => {
+                                push_err(&mut errors, "MITM_DETECTED");
+                            }
+                        }
+                    }
+                }
+            }
             "publish" => {
                 if let (Some(pid), Some(track_id)) = (&ev.participant, &ev.track_id) {
                     if !authed.contains(pid) {
@@ -224,6 +334,33 @@ fn simulate(s: &Scenario) -> SimulationResult {
                 push_err(&mut errors, "STALE_KEY_REUSE");
                 key_leak_attempts += 1;
             }
+            "media_frame" => {
+                if let (Some(track_id), Some(key_epoch), Some(record_seq)) =
+                    (&ev.track_id, ev.key_epoch, ev.record_seq)
+                {
+                    let state = track_keys.entry(track_id.clone()).or_default();
+
+                    if state.seen_records.is_empty() {
+                        state.highest_epoch = key_epoch;
+                    } else if key_epoch < state.highest_epoch {
+                        push_err(&mut errors, "STALE_KEY_REUSE");
+                        key_leak_attempts += 1;
+                    } else if key_epoch > state.highest_epoch {
+                        state.highest_epoch = key_epoch;
+                    }
+
+                    if !state.seen_records.insert((key_epoch, record_seq)) {
+                        push_err(&mut errors, "NONCE_REUSE");
+                        nonce_reuse_events += 1;
+                    } else {
+                        // Real nonce = base_nonce XOR record_seq; computing it
+                        // here (even though we don't AEAD-seal anything in the
+                        // simulator) keeps the key schedule honest about what
+                        // a real stack would feed its cipher.
+                        let _nonce = derive_base_nonce(track_id, key_epoch) ^ (record_seq as u64);
+                    }
+                }
+            }
             "steal_key" => {
                 push_err(&mut errors, "KEY_LEAK_ATTEMPT");
                 key_leak_attempts += 1;
@@ -252,6 +389,7 @@ fn simulate(s: &Scenario) -> SimulationResult {
         "false_negative_leaks": false_negative_leaks,
         "max_extra_latency_ms": detection_time.unwrap_or(0),
         "affected_participant_count": affected.len(),
+        "nonce_reuse_events": nonce_reuse_events,
     });
 
     SimulationResult {
@@ -311,6 +449,9 @@ fn evaluate(exp: &Expectations, res: &SimulationResult) -> (String, Vec<String>)
     if res.metrics["false_negative_leaks"].as_i64().unwrap_or(0) as i32 > exp.max_false_negative_leaks {
         failures.push("false_negative_leaks_exceeded".into());
     }
+    if res.metrics["nonce_reuse_events"].as_i64().unwrap_or(0) as i32 > exp.max_nonce_reuse {
+        failures.push("nonce_reuse_exceeded".into());
+    }
 
     if !exp.residual_routing_allowed {
         if res.metrics["duplicate_routes"].as_i64().unwrap_or(0) > 0 {